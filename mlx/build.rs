@@ -1,18 +1,59 @@
+use std::env;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Explains how to get a working minilibx, after what went wrong.
+fn fail(problem: &str, dir: &Path) -> ! {
+    panic!(
+        "\n\n{}\n\n\
+         The minilibx is expected in {dir}, which must contain its Makefile and builds {lib}.\n\
+         To fix this:\n\
+         \x20 - clone it there: git clone https://github.com/42Paris/minilibx-linux {dir}\n\
+         \x20 - or point MLX_DIR to an existing copy: MLX_DIR=/path/to/minilibx cargo build\n\
+         \x20 - install the X11 development headers it needs, e.g. libx11-dev and libxext-dev\n\
+         \x20 - check that `make -C {dir}` builds {lib}\n\n",
+        problem,
+        dir = dir.display(),
+        lib = dir.join("libmlx.a").display(),
+    );
+}
+
 fn main() {
+    println!("cargo:rerun-if-env-changed=MLX_DIR");
+    let dir = match env::var_os("MLX_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("../minilibx"),
+    };
+
+    let (makefile, lib) = (dir.join("Makefile"), dir.join("libmlx.a"));
+    if !makefile.is_file() {
+        fail(
+            &format!("No minilibx Makefile found at {}.", makefile.display()),
+            &dir,
+        );
+    }
+
     let status = Command::new("make")
-        .current_dir("../minilibx")
+        .current_dir(&dir)
         .arg("all")
         .env("CFLAGS", "-fPIC")
         .status()
-        .expect("Failed to execute make command");
+        .unwrap_or_else(|e| fail(&format!("Failed to run make: {}.", e), &dir));
 
     if !status.success() {
-        panic!("Failed to execute make command");
+        fail(
+            &format!("make failed in {} ({}).", dir.display(), status),
+            &dir,
+        );
+    }
+    if !lib.is_file() {
+        fail(
+            &format!("make succeeded but did not build {}.", lib.display()),
+            &dir,
+        );
     }
 
-    println!("cargo:rustc-link-search=./minilibx/");
+    println!("cargo:rustc-link-search={}", dir.display());
     println!("cargo:rustc-link-lib=mlx");
     println!("cargo:rustc-link-lib=Xext");
     println!("cargo:rustc-link-lib=X11");
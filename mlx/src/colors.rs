@@ -0,0 +1,111 @@
+//! Colors, and the 16 basic colors of HTML as `0xRRGGBB` values.
+//!
+//! Usage:
+//!```
+//! use minilibx::colors::{self, Color};
+//!
+//! image.fill_rect(10, 10, 100, 50, colors::RED);
+//! mlx.string_put(&window, 10, 80, Color::rgb(255, 128, 0), "Hello World");
+//!```
+
+/// A `0xRRGGBB` color.
+///
+/// The drawing functions take any `impl Into<Color>`, so the `u32` and `i32` values
+/// like the constants of this module can be given directly.
+///
+/// Usage:
+///```
+/// let orange = Color::rgb(255, 128, 0);
+/// assert_eq!(orange.as_u32(), 0xff8000);
+/// assert_eq!(Color::hsv(120.0, 1.0, 1.0), Color::from(colors::LIME));
+///```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Color(pub u32);
+
+impl Color {
+    /// Color of the red, green and blue channels.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self((r as u32) << 16 | (g as u32) << 8 | b as u32)
+    }
+
+    /// Color of a hue `h` in degrees, wrapping around 360, a saturation `s` and a value `v` in `[0, 1]`.
+    ///
+    /// The saturation and the value are clamped to `[0, 1]`.
+    pub fn hsv(h: f32, s: f32, v: f32) -> Self {
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+        let h = h.rem_euclid(360.0) / 60.0;
+        let chroma = v * s;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |c: f32| ((c + v - chroma) * 255.0).round() as u8;
+        Self::rgb(channel(r), channel(g), channel(b))
+    }
+
+    /// The color as a `0xRRGGBB` value, as stored in the images.
+    pub const fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// The color as a `0xRRGGBB` value, as taken by the minilibx functions drawing on windows.
+    pub const fn as_i32(self) -> i32 {
+        self.0 as i32
+    }
+}
+
+impl From<u32> for Color {
+    fn from(color: u32) -> Self {
+        Self(color)
+    }
+}
+
+impl From<i32> for Color {
+    fn from(color: i32) -> Self {
+        Self(color as u32)
+    }
+}
+
+impl From<Color> for u32 {
+    fn from(color: Color) -> Self {
+        color.as_u32()
+    }
+}
+
+/// `#000000`
+pub const BLACK: u32 = 0x000000;
+/// `#c0c0c0`
+pub const SILVER: u32 = 0xc0c0c0;
+/// `#808080`
+pub const GRAY: u32 = 0x808080;
+/// `#ffffff`
+pub const WHITE: u32 = 0xffffff;
+/// `#800000`
+pub const MAROON: u32 = 0x800000;
+/// `#ff0000`
+pub const RED: u32 = 0xff0000;
+/// `#800080`
+pub const PURPLE: u32 = 0x800080;
+/// `#ff00ff`
+pub const FUCHSIA: u32 = 0xff00ff;
+/// `#008000`
+pub const GREEN: u32 = 0x008000;
+/// `#00ff00`
+pub const LIME: u32 = 0x00ff00;
+/// `#808000`
+pub const OLIVE: u32 = 0x808000;
+/// `#ffff00`
+pub const YELLOW: u32 = 0xffff00;
+/// `#000080`
+pub const NAVY: u32 = 0x000080;
+/// `#0000ff`
+pub const BLUE: u32 = 0x0000ff;
+/// `#008080`
+pub const TEAL: u32 = 0x008080;
+/// `#00ffff`
+pub const AQUA: u32 = 0x00ffff;
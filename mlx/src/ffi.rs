@@ -1,11 +1,26 @@
-use std::ffi::{c_void, CString};
-use std::os::raw::c_char;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::ffi::{c_void, CString, NulError};
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_long, c_uint, c_ulong};
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::ptr;
+use std::sync::{Mutex, OnceLock};
 
 fn create_c_str(s: &str) -> Result<CString, MlxError> {
-    match CString::new(s) {
-        Ok(s) => Ok(s),
-        Err(_) => Err(MlxError::Any(format!("Error creating string {}", s))),
+    Ok(CString::new(s)?)
+}
+
+/// Checks a size before it is given to the minilibx, which does not handle empty or negative sizes.
+fn check_size(what: &str, width: i32, height: i32) -> Result<(), MlxError> {
+    if width <= 0 || height <= 0 {
+        return Err(MlxError::Any(format!(
+            "Invalid {} size {}x{}, the width and height must be positive.",
+            what, width, height
+        )));
     }
+    Ok(())
 }
 
 /// Enum for detecting errors in the minilibx.
@@ -15,10 +30,45 @@ pub enum MlxError {
     Init,
     /// Error that could happen when mlx_new_window returns a null pointer
     Window,
+    /// Error that happens when a string given to the minilibx contains a NUL byte.
+    ///
+    /// The minilibx takes C strings, which end at the first NUL byte, so they cannot contain one.
+    /// The original error tells where the NUL byte was found.
+    Nul(NulError),
     /// Error that might happen in all other places. It contains the information about the error.
     Any(String),
 }
 
+impl fmt::Display for MlxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MlxError::Init => write!(f, "cannot connect to the X server, is DISPLAY set?"),
+            MlxError::Window => write!(f, "cannot create the window"),
+            MlxError::Nul(e) => write!(
+                f,
+                "the string given to the minilibx contains a NUL byte: {}",
+                e
+            ),
+            MlxError::Any(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl Error for MlxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MlxError::Nul(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<NulError> for MlxError {
+    fn from(e: NulError) -> Self {
+        MlxError::Nul(e)
+    }
+}
+
 pub fn init() -> Result<*mut c_void, MlxError> {
     extern "C" {
         pub fn mlx_init() -> *mut c_void;
@@ -60,6 +110,7 @@ pub fn new_window(
         ) -> *mut c_void;
     }
 
+    check_size("window", size_x, size_y)?;
     let title = create_c_str(title)?;
 
     unsafe {
@@ -153,6 +204,7 @@ pub fn new_image(mlx_ptr: *mut c_void, width: i32, height: i32) -> Result<*mut c
         fn mlx_new_image(mlx_ptr: *mut c_void, width: i32, height: i32) -> *mut c_void;
     }
 
+    check_size("image", width, height)?;
     unsafe {
         let ptr = mlx_new_image(mlx_ptr, width, height);
         if ptr.is_null() {
@@ -192,7 +244,7 @@ pub fn xpm_to_image(mlx_ptr: *mut c_void, xpm_data: Vec<String>) -> Result<XpmIm
             &mut height,
         );
         if ptr.is_null() {
-            Err(MlxError::Any(format!("Error creating xpm image.",)))
+            Err(MlxError::Any("Error creating xpm image.".to_string()))
         } else {
             Ok(XpmImage { ptr, width, height })
         }
@@ -215,7 +267,7 @@ pub fn xpm_file_to_image(mlx_ptr: *mut c_void, filename: &str) -> Result<XpmImag
     unsafe {
         let ptr = mlx_xpm_file_to_image(mlx_ptr, filename.as_ptr(), &mut width, &mut height);
         if ptr.is_null() {
-            Err(MlxError::Any(format!("Error creating xpm image.",)))
+            Err(MlxError::Any("Error creating xpm image.".to_string()))
         } else {
             Ok(XpmImage { ptr, width, height })
         }
@@ -256,9 +308,9 @@ pub fn get_data_addr(img_ptr: *mut c_void) -> Result<AddrData, MlxError> {
     unsafe {
         area = mlx_get_data_addr(img_ptr, &mut bits_per_pixel, &mut size_line, &mut endian);
         if area.is_null() {
-            Err(MlxError::Any(format!(
-                "Error when trying to access image data"
-            )))
+            Err(MlxError::Any(
+                "Error when trying to access image data".to_string(),
+            ))
         } else {
             Ok(AddrData {
                 area,
@@ -292,6 +344,24 @@ pub fn put_image_to_window(
     }
 }
 
+pub fn do_sync(mlx_ptr: *mut c_void) {
+    extern "C" {
+        fn mlx_do_sync(mlx_ptr: *mut c_void) -> i32;
+    }
+
+    unsafe {
+        mlx_do_sync(mlx_ptr);
+    }
+}
+
+pub fn events_pending(mlx_ptr: *mut c_void) -> bool {
+    extern "C" {
+        fn XPending(display: *mut c_void) -> i32;
+    }
+
+    unsafe { XPending(display(mlx_ptr)) > 0 }
+}
+
 pub fn get_color_value(mlx_ptr: *mut c_void, color: i32) -> u32 {
     extern "C" {
         fn mlx_get_color_value(mlx_ptr: *mut c_void, color: i32) -> u32;
@@ -350,17 +420,26 @@ pub fn mouse_show(mlx_ptr: *mut c_void, win_ptr: *mut c_void) {
     }
 }
 
-pub fn event_loop(mlx_ptr: *mut c_void) {
+/// Runs `mlx_loop`, returning its status: 0 once `mlx_loop_end` was called or the last window was destroyed.
+pub fn event_loop(mlx_ptr: *mut c_void) -> i32 {
     extern "C" {
         fn mlx_loop(mlx_ptr: *mut c_void) -> i32;
     }
 
+    unsafe { mlx_loop(mlx_ptr) }
+}
+
+pub fn loop_end(mlx_ptr: *mut c_void) {
+    extern "C" {
+        fn mlx_loop_end(mlx_ptr: *mut c_void) -> i32;
+    }
+
     unsafe {
-        mlx_loop(mlx_ptr);
+        mlx_loop_end(mlx_ptr);
     }
 }
 
-pub fn mouse_hook<T>(win_ptr: *mut c_void, cb: T)
+pub fn mouse_hook<T>(win_ptr: *mut c_void, cb: T) -> Callback
 where
     T: FnMut(i32, i32, i32) + 'static,
 {
@@ -378,16 +457,17 @@ where
     {
         let callback_ptr = data as *mut F;
         let callback = &mut *callback_ptr;
-        callback(buttons, x, y);
+        guard(|| callback(buttons, x, y));
     }
 
-    let callback = Box::into_raw(Box::new(cb));
+    let callback = Callback::new(cb);
     unsafe {
-        mlx_mouse_hook(win_ptr, call_closure::<T>, callback as *mut c_void);
+        mlx_mouse_hook(win_ptr, call_closure::<T>, callback.data);
     }
+    callback
 }
 
-pub fn key_hook<F>(win_ptr: *mut c_void, cb: F)
+pub fn key_hook<F>(win_ptr: *mut c_void, cb: F) -> Callback
 where
     F: FnMut(i32) + 'static,
 {
@@ -405,16 +485,17 @@ where
     {
         let callback_ptr = data as *mut F;
         let callback = &mut *callback_ptr;
-        callback(keycode);
+        guard(|| callback(keycode));
     }
 
-    let callback = Box::into_raw(Box::new(cb));
+    let callback = Callback::new(cb);
     unsafe {
-        mlx_key_hook(win_ptr, call_closure::<F>, callback as *mut c_void);
+        mlx_key_hook(win_ptr, call_closure::<F>, callback.data);
     }
+    callback
 }
 
-pub fn expose_hook<F>(win_ptr: *mut c_void, cb: F)
+pub fn expose_hook<F>(win_ptr: *mut c_void, cb: F) -> Callback
 where
     F: FnMut() + 'static,
 {
@@ -432,73 +513,711 @@ where
     {
         let callback_ptr = data as *mut F;
         let callback = &mut *callback_ptr;
-        callback();
+        guard(callback);
     }
 
-    let callback = Box::into_raw(Box::new(cb));
+    let callback = Callback::new(cb);
     unsafe {
-        mlx_expose_hook(win_ptr, call_closure::<F>, callback as *mut c_void);
+        mlx_expose_hook(win_ptr, call_closure::<F>, callback.data);
     }
+    callback
+}
+
+extern "C" {
+    fn mlx_loop_hook(
+        mlx_ptr: *mut c_void,
+        func_ptr: Option<unsafe extern "C" fn(*mut c_void)>,
+        param: *mut c_void,
+    ) -> i32;
 }
 
-pub fn loop_hook<F>(mlx_ptr: *mut c_void, cb: F)
+pub fn loop_hook<F>(mlx_ptr: *mut c_void, cb: F) -> Callback
 where
     F: FnMut() + 'static,
 {
-    extern "C" {
-        fn mlx_loop_hook(
-            mlx_ptr: *mut c_void,
-            func_ptr: unsafe extern "C" fn(*mut c_void),
-            param: *mut c_void,
-        ) -> i32;
-    }
-
     unsafe extern "C" fn call_closure<F>(data: *mut c_void)
     where
         F: FnMut(),
     {
         let callback_ptr = data as *mut F;
         let callback = &mut *callback_ptr;
-        callback();
+        guard(callback);
+    }
+
+    let callback = Callback::new(cb);
+    unsafe {
+        mlx_loop_hook(mlx_ptr, Some(call_closure::<F>), callback.data);
+    }
+    callback
+}
+
+/// Removes the loop hook, the minilibx skips it when its function is null.
+pub fn unhook_loop(mlx_ptr: *mut c_void) {
+    unsafe {
+        mlx_loop_hook(mlx_ptr, None, ptr::null_mut());
+    }
+}
+
+/// Closure given to the minilibx as the data of a hook, freed when it is dropped.
+///
+/// The hook must not run anymore by then: it is removed, or its window destroyed.
+pub struct Callback {
+    data: *mut c_void,
+    free: unsafe fn(*mut c_void),
+}
+
+impl Callback {
+    fn new<T>(value: T) -> Self {
+        unsafe fn free<T>(data: *mut c_void) {
+            drop(Box::from_raw(data as *mut T));
+        }
+
+        Self {
+            data: Box::into_raw(Box::new(value)) as *mut c_void,
+            free: free::<T>,
+        }
+    }
+}
+
+impl Drop for Callback {
+    fn drop(&mut self) {
+        unsafe { (self.free)(self.data) }
+    }
+}
+
+/// Runs a hook callback, aborting the process if it panics.
+///
+/// Unwinding out of a hook would go through the C code of the minilibx, which is undefined behavior.
+fn guard(callback: impl FnOnce()) {
+    if panic::catch_unwind(AssertUnwindSafe(callback)).is_err() {
+        eprintln!("a minilibx hook panicked, aborting");
+        process::abort();
     }
+}
+
+// The minilibx calls the hook function with different arguments depending on the event,
+// so the function pointer is left untyped here.
+extern "C" {
+    fn mlx_hook(
+        win_ptr: *mut c_void,
+        x_event: i32,
+        x_mask: i32,
+        func_ptr: *const c_void,
+        param: *mut c_void,
+    ) -> i32;
+}
+
+/// Events from `X11/X.h` the minilibx passes arguments for, and ConfigureNotify.
+pub const KEY_PRESS: i32 = 2;
+pub const KEY_RELEASE: i32 = 3;
+pub const BUTTON_PRESS: i32 = 4;
+/// ButtonRelease event and ButtonReleaseMask from `X11/X.h`.
+pub const BUTTON_RELEASE: i32 = 5;
+pub const BUTTON_RELEASE_MASK: i32 = 1 << 3;
+/// DestroyNotify event from `X11/X.h`, which the minilibx also sends for the close button of the window manager.
+pub const DESTROY_NOTIFY: i32 = 17;
+pub const MOTION_NOTIFY: i32 = 6;
+/// Expose event from `X11/X.h`, hooked by `mlx_expose_hook`.
+pub const EXPOSE: i32 = 12;
+pub const CONFIGURE_NOTIFY: i32 = 22;
+/// StructureNotifyMask from `X11/X.h`, which selects ConfigureNotify.
+pub const STRUCTURE_NOTIFY_MASK: i32 = 1 << 17;
+
+/// KeyPressMask and PointerMotionMask from `X11/X.h`.
+const KEY_PRESS_MASK: i32 = 1 << 0;
+const POINTER_MOTION_MASK: i32 = 1 << 6;
+
+/// Fields of the X event given to a [hook](struct.MlxWindow.html#method.hook), depending on its type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XEventData {
+    /// `KeyPress` (2) and `KeyRelease` (3): the keysym of the key.
+    Key {
+        /// keysym, e.g. `0xff1b` for escape
+        keycode: i32,
+    },
+    /// `ButtonPress` (4) and `ButtonRelease` (5): the button and the pointer position in the window.
+    Button {
+        /// 1 to 3 from left to right, 4 and 5 for the wheel
+        button: i32,
+        /// column of the pointer
+        x: i32,
+        /// row of the pointer
+        y: i32,
+    },
+    /// `MotionNotify` (6): the pointer position in the window.
+    Motion {
+        /// column of the pointer
+        x: i32,
+        /// row of the pointer
+        y: i32,
+    },
+    /// `ConfigureNotify` (22): the size of the window.
+    ///
+    /// The minilibx does not pass the event along, so the size is read from the X server when the hook runs.
+    Configure {
+        /// width of the window in pixels
+        width: i32,
+        /// height of the window in pixels
+        height: i32,
+    },
+    /// Any other event, the minilibx does not pass its fields.
+    Other,
+}
+
+/// Closure of a generic hook, with the window to query when the minilibx gives no data.
+struct Hook<F> {
+    callback: F,
+    mlx_ptr: *mut c_void,
+    win_ptr: *mut c_void,
+}
 
-    let callback = Box::into_raw(Box::new(cb));
+pub fn hook<F>(
+    mlx_ptr: *mut c_void,
+    win_ptr: *mut c_void,
+    x_event: i32,
+    x_mask: i32,
+    cb: F,
+) -> Callback
+where
+    F: FnMut(XEventData) + 'static,
+{
+    // the minilibx passes different arguments depending on the event
+    unsafe extern "C" fn key<F>(keycode: i32, data: *mut c_void)
+    where
+        F: FnMut(XEventData),
+    {
+        let hook = &mut *(data as *mut Hook<F>);
+        guard(|| (hook.callback)(XEventData::Key { keycode }));
+    }
+    unsafe extern "C" fn button<F>(button: i32, x: i32, y: i32, data: *mut c_void)
+    where
+        F: FnMut(XEventData),
+    {
+        let hook = &mut *(data as *mut Hook<F>);
+        guard(|| (hook.callback)(XEventData::Button { button, x, y }));
+    }
+    unsafe extern "C" fn motion<F>(x: i32, y: i32, data: *mut c_void)
+    where
+        F: FnMut(XEventData),
+    {
+        let hook = &mut *(data as *mut Hook<F>);
+        guard(|| (hook.callback)(XEventData::Motion { x, y }));
+    }
+    unsafe extern "C" fn configure<F>(data: *mut c_void)
+    where
+        F: FnMut(XEventData),
+    {
+        let hook = &mut *(data as *mut Hook<F>);
+        let (_, _, width, height) = window_geometry(hook.mlx_ptr, hook.win_ptr);
+        guard(|| (hook.callback)(XEventData::Configure { width, height }));
+    }
+    unsafe extern "C" fn other<F>(data: *mut c_void)
+    where
+        F: FnMut(XEventData),
+    {
+        let hook = &mut *(data as *mut Hook<F>);
+        guard(|| (hook.callback)(XEventData::Other));
+    }
+
+    let func_ptr = match x_event {
+        KEY_PRESS | KEY_RELEASE => key::<F> as *const c_void,
+        BUTTON_PRESS | BUTTON_RELEASE => button::<F> as *const c_void,
+        MOTION_NOTIFY => motion::<F> as *const c_void,
+        CONFIGURE_NOTIFY => configure::<F> as *const c_void,
+        _ => other::<F> as *const c_void,
+    };
+    let hook = Callback::new(Hook {
+        callback: cb,
+        mlx_ptr,
+        win_ptr,
+    });
+    unsafe {
+        mlx_hook(win_ptr, x_event, x_mask, func_ptr, hook.data);
+    }
+    hook
+}
+
+/// Removes the hook of the `x_event` events of a window, the minilibx skips it when its function is null.
+pub fn unhook(win_ptr: *mut c_void, x_event: i32) {
     unsafe {
-        mlx_loop_hook(mlx_ptr, call_closure::<F>, callback as *mut c_void);
+        mlx_hook(win_ptr, x_event, 0, ptr::null(), ptr::null_mut());
     }
 }
 
-pub fn hook<F>(win_ptr: *mut c_void, x_event: i32, x_mask: i32, cb: F)
+pub fn key_press_hook<F>(mlx_ptr: *mut c_void, win_ptr: *mut c_void, cb: F) -> Callback
 where
-    F: FnMut(),
+    F: FnMut(i32, u32) + 'static,
 {
+    // the minilibx passes the keysym of the pressed key, the state mask was kept when Xlib read the event
+    unsafe extern "C" fn call_closure<F>(keycode: i32, data: *mut c_void)
+    where
+        F: FnMut(i32, u32),
+    {
+        let hook = &mut *(data as *mut Hook<F>);
+        let state = take_key_state(x_window(hook.win_ptr))
+            .unwrap_or_else(|| query_pointer_state(hook.mlx_ptr, hook.win_ptr));
+        guard(|| (hook.callback)(keycode, state));
+    }
+
+    record_key_states(mlx_ptr);
+    let hook = Callback::new(Hook {
+        callback: cb,
+        mlx_ptr,
+        win_ptr,
+    });
+    unsafe {
+        mlx_hook(
+            win_ptr,
+            KEY_PRESS,
+            KEY_PRESS_MASK,
+            call_closure::<F> as *const c_void,
+            hook.data,
+        );
+    }
+    hook
+}
+
+/// The fields of the `XKeyEvent` struct of `X11/Xlib.h`, which starts the `XEvent` union for key events.
+#[repr(C)]
+struct XKeyEvent {
+    kind: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: *mut c_void,
+    window: c_ulong,
+    root: c_ulong,
+    subwindow: c_ulong,
+    time: c_ulong,
+    x: c_int,
+    y: c_int,
+    x_root: c_int,
+    y_root: c_int,
+    state: c_uint,
+    keycode: c_uint,
+    same_screen: c_int,
+}
+
+/// Function converting an event from the X protocol to an `XEvent`, see `XESetWireToEvent` in `X11/Xlibint.h`.
+type WireToEvent =
+    unsafe extern "C" fn(display: *mut c_void, event: *mut XKeyEvent, wire: *mut c_void) -> c_int;
+
+/// Most KeyPress states kept for the hooks, see [push_key_state].
+const MAX_KEY_STATES: usize = 64;
+
+/// Converter of Xlib for the KeyPress events, wrapped by [record_key_state].
+static WIRE_TO_KEY_EVENT: OnceLock<WireToEvent> = OnceLock::new();
+/// Window and state mask of the KeyPress events read from the X server, oldest first.
+static KEY_STATES: Mutex<VecDeque<(c_ulong, u32)>> = Mutex::new(VecDeque::new());
+
+/// Has Xlib run [record_key_state] on the KeyPress events of the display of `mlx_ptr`.
+///
+/// The minilibx only gives the keysym to the KeyPress hooks, not the event and its state mask.
+/// Xlib converts the events in the order it reads them, which is the order the minilibx hands them to the hooks,
+/// so the hooks take the recorded states in turn with [take_key_state].
+fn record_key_states(mlx_ptr: *mut c_void) {
     extern "C" {
-        fn mlx_hook(
-            win_ptr: *mut c_void,
-            x_event: i32,
-            x_mask: i32,
-            func_ptr: unsafe extern "C" fn(*mut c_void),
-            param: *mut c_void,
-        ) -> i32;
+        fn XESetWireToEvent(
+            display: *mut c_void,
+            event_number: c_int,
+            proc_: WireToEvent,
+        ) -> Option<WireToEvent>;
     }
 
-    unsafe extern "C" fn call_closure<F>(data: *mut c_void)
+    let previous = unsafe { XESetWireToEvent(display(mlx_ptr), KEY_PRESS, record_key_state) };
+    match previous {
+        // already recording, from another key press hook
+        Some(previous) if previous as usize == record_key_state as WireToEvent as usize => {}
+        Some(previous) => {
+            // the converter of Xlib is the same for every display
+            let _ = WIRE_TO_KEY_EVENT.set(previous);
+        }
+        None => {}
+    }
+}
+
+/// Converts a KeyPress event with the converter of Xlib, keeping its window and state mask.
+unsafe extern "C" fn record_key_state(
+    display: *mut c_void,
+    event: *mut XKeyEvent,
+    wire: *mut c_void,
+) -> c_int {
+    extern "C" {
+        /// The default converter of Xlib, from `X11/Xlibint.h`.
+        fn _XWireToEvent(display: *mut c_void, event: *mut XKeyEvent, wire: *mut c_void) -> c_int;
+    }
+
+    let convert = WIRE_TO_KEY_EVENT.get().copied().unwrap_or(_XWireToEvent);
+    let converted = convert(display, event, wire);
+    if converted != 0 {
+        push_key_state((*event).window, (*event).state);
+    }
+    converted
+}
+
+/// Records the state mask of a KeyPress event of `window`.
+///
+/// Past [MAX_KEY_STATES], the recorded states were not taken by hooks and may not match the next events anymore:
+/// they are dropped, and the hooks of these events query the pointer instead.
+fn push_key_state(window: c_ulong, state: u32) {
+    let mut states = KEY_STATES.lock().unwrap();
+    if states.len() == MAX_KEY_STATES {
+        states.clear();
+    }
+    states.push_back((window, state));
+}
+
+/// State mask of the oldest recorded KeyPress event of `window`, `None` when there is none.
+///
+/// The states of the other windows recorded before it are dropped: their events were handed out first,
+/// to windows without a KeyPress hook.
+fn take_key_state(window: c_ulong) -> Option<u32> {
+    let mut states = KEY_STATES.lock().unwrap();
+    while let Some((from, state)) = states.pop_front() {
+        if from == window {
+            return Some(state);
+        }
+    }
+    None
+}
+
+pub fn button_hook<F>(win_ptr: *mut c_void, x_event: i32, x_mask: i32, cb: F) -> Callback
+where
+    F: FnMut(i32, i32, i32) + 'static,
+{
+    // the minilibx passes the button and the pointer position
+    unsafe extern "C" fn call_closure<F>(button: i32, x: i32, y: i32, data: *mut c_void)
     where
-        F: FnMut(),
+        F: FnMut(i32, i32, i32),
     {
         let callback_ptr = data as *mut F;
         let callback = &mut *callback_ptr;
-        callback();
+        guard(|| callback(button, x, y));
     }
 
-    let callback = Box::into_raw(Box::new(cb));
+    let callback = Callback::new(cb);
     unsafe {
         mlx_hook(
             win_ptr,
             x_event,
             x_mask,
-            call_closure::<F>,
-            callback as *mut c_void,
+            call_closure::<F> as *const c_void,
+            callback.data,
+        );
+    }
+    callback
+}
+
+pub fn motion_hook<F>(win_ptr: *mut c_void, cb: F) -> Callback
+where
+    F: FnMut(i32, i32) + 'static,
+{
+    // the minilibx passes the pointer position
+    unsafe extern "C" fn call_closure<F>(x: i32, y: i32, data: *mut c_void)
+    where
+        F: FnMut(i32, i32),
+    {
+        let callback_ptr = data as *mut F;
+        let callback = &mut *callback_ptr;
+        guard(|| callback(x, y));
+    }
+
+    let callback = Callback::new(cb);
+    unsafe {
+        mlx_hook(
+            win_ptr,
+            MOTION_NOTIFY,
+            POINTER_MOTION_MASK,
+            call_closure::<F> as *const c_void,
+            callback.data,
+        );
+    }
+    callback
+}
+
+/// The X11 connection, first field of the minilibx `t_xvar` struct.
+unsafe fn display(mlx_ptr: *mut c_void) -> *mut c_void {
+    *(mlx_ptr as *const *mut c_void)
+}
+
+/// The X11 window id, first field of the minilibx `t_win_list` struct.
+unsafe fn x_window(win_ptr: *mut c_void) -> c_ulong {
+    *(win_ptr as *const c_ulong)
+}
+
+pub fn set_window_title(
+    mlx_ptr: *mut c_void,
+    win_ptr: *mut c_void,
+    title: &str,
+) -> Result<(), MlxError> {
+    extern "C" {
+        fn XStoreName(display: *mut c_void, window: c_ulong, window_name: *const c_char) -> i32;
+    }
+
+    let title = create_c_str(title)?;
+    unsafe {
+        XStoreName(display(mlx_ptr), x_window(win_ptr), title.as_ptr());
+    }
+    Ok(())
+}
+
+/// `XSizeHints` from `X11/Xutil.h`.
+#[repr(C)]
+#[derive(Default)]
+struct XSizeHints {
+    flags: c_long,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    min_width: i32,
+    min_height: i32,
+    max_width: i32,
+    max_height: i32,
+    width_inc: i32,
+    height_inc: i32,
+    min_aspect: [i32; 2],
+    max_aspect: [i32; 2],
+    base_width: i32,
+    base_height: i32,
+    win_gravity: i32,
+}
+
+/// Removes the minimum and maximum sizes the minilibx gives to the window manager, which keep the window from being resized.
+pub fn allow_resize(mlx_ptr: *mut c_void, win_ptr: *mut c_void) {
+    extern "C" {
+        fn XSetWMNormalHints(display: *mut c_void, window: c_ulong, hints: *const XSizeHints);
+    }
+
+    // no flags: none of the hints are set
+    let hints = XSizeHints::default();
+    unsafe {
+        XSetWMNormalHints(display(mlx_ptr), x_window(win_ptr), &hints);
+    }
+}
+
+pub fn move_window(mlx_ptr: *mut c_void, win_ptr: *mut c_void, x: i32, y: i32) {
+    extern "C" {
+        fn XMoveWindow(display: *mut c_void, window: c_ulong, x: i32, y: i32) -> i32;
+    }
+
+    unsafe {
+        XMoveWindow(display(mlx_ptr), x_window(win_ptr), x, y);
+    }
+}
+
+/// Position of a window in its parent, and its size: `(x, y, width, height)`.
+pub fn window_geometry(mlx_ptr: *mut c_void, win_ptr: *mut c_void) -> (i32, i32, i32, i32) {
+    let (mut root, mut x, mut y) = (0, 0, 0);
+    let (mut width, mut height, mut border, mut depth) = (0, 0, 0, 0);
+    unsafe {
+        XGetGeometry(
+            display(mlx_ptr),
+            x_window(win_ptr),
+            &mut root,
+            &mut x,
+            &mut y,
+            &mut width,
+            &mut height,
+            &mut border,
+            &mut depth,
         );
     }
+    (x, y, width as i32, height as i32)
+}
+
+extern "C" {
+    fn XGetGeometry(
+        display: *mut c_void,
+        drawable: c_ulong,
+        root_return: &mut c_ulong,
+        x_return: &mut i32,
+        y_return: &mut i32,
+        width_return: &mut u32,
+        height_return: &mut u32,
+        border_width_return: &mut u32,
+        depth_return: &mut u32,
+    ) -> i32;
+    fn XDefaultRootWindow(display: *mut c_void) -> c_ulong;
+}
+
+/// Pixels of a window, as `0xRRGGBB` values row after row.
+pub struct Capture {
+    pub width: i32,
+    pub height: i32,
+    pub pixels: Vec<u32>,
+}
+
+/// Reads the pixels of the part of a window that lies on the screen, the other ones are left black.
+pub fn capture_window(mlx_ptr: *mut c_void, win_ptr: *mut c_void) -> Result<Capture, MlxError> {
+    extern "C" {
+        fn XTranslateCoordinates(
+            display: *mut c_void,
+            src_w: c_ulong,
+            dest_w: c_ulong,
+            src_x: i32,
+            src_y: i32,
+            dest_x_return: &mut i32,
+            dest_y_return: &mut i32,
+            child_return: &mut c_ulong,
+        ) -> i32;
+        fn XGetImage(
+            display: *mut c_void,
+            drawable: c_ulong,
+            x: i32,
+            y: i32,
+            width: u32,
+            height: u32,
+            plane_mask: c_ulong,
+            format: i32,
+        ) -> *mut c_void;
+        fn XGetPixel(ximage: *mut c_void, x: i32, y: i32) -> c_ulong;
+        fn XDestroyImage(ximage: *mut c_void) -> i32;
+    }
+    const ALL_PLANES: c_ulong = !0;
+    const Z_PIXMAP: i32 = 2;
+
+    let (display, window) = unsafe { (display(mlx_ptr), x_window(win_ptr)) };
+    let (_, _, width, height) = window_geometry(mlx_ptr, win_ptr);
+    let (mut root_x, mut root_y, mut child) = (0, 0, 0);
+    unsafe {
+        let root = XDefaultRootWindow(display);
+        XTranslateCoordinates(
+            display,
+            window,
+            root,
+            0,
+            0,
+            &mut root_x,
+            &mut root_y,
+            &mut child,
+        );
+    }
+
+    // X fails on the parts of the window outside of the screen
+    let (screen_width, screen_height) = get_screen_size(mlx_ptr);
+    let (left, top) = ((-root_x).max(0), (-root_y).max(0));
+    let right = width.min(screen_width - root_x);
+    let bottom = height.min(screen_height - root_y);
+    if left >= right || top >= bottom {
+        return Err(MlxError::Any(
+            "The window is outside of the screen".to_string(),
+        ));
+    }
+
+    let (visible_width, visible_height) = ((right - left) as u32, (bottom - top) as u32);
+    let image = unsafe {
+        XGetImage(
+            display,
+            window,
+            left,
+            top,
+            visible_width,
+            visible_height,
+            ALL_PLANES,
+            Z_PIXMAP,
+        )
+    };
+    if image.is_null() {
+        return Err(MlxError::Any(
+            "Error when reading the window pixels".to_string(),
+        ));
+    }
+
+    let mut pixels = vec![0; (width * height) as usize];
+    for py in top..bottom {
+        for px in left..right {
+            let pixel = unsafe { XGetPixel(image, px - left, py - top) };
+            pixels[(py * width + px) as usize] = pixel as u32 & 0xffffff;
+        }
+    }
+    unsafe {
+        XDestroyImage(image);
+    }
+    Ok(Capture {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Returns the current modifier keys and pointer buttons state of the X server.
+///
+/// Used by the key press hooks when the state of their event was not recorded, see [record_key_states].
+pub fn query_pointer_state(mlx_ptr: *mut c_void, win_ptr: *mut c_void) -> u32 {
+    extern "C" {
+        fn XQueryPointer(
+            display: *mut c_void,
+            window: c_ulong,
+            root_return: &mut c_ulong,
+            child_return: &mut c_ulong,
+            root_x_return: &mut i32,
+            root_y_return: &mut i32,
+            win_x_return: &mut i32,
+            win_y_return: &mut i32,
+            mask_return: &mut u32,
+        ) -> i32;
+    }
+
+    let mut root = 0;
+    let mut child = 0;
+    let (mut root_x, mut root_y, mut win_x, mut win_y) = (0, 0, 0, 0);
+    let mut mask = 0;
+    unsafe {
+        XQueryPointer(
+            display(mlx_ptr),
+            x_window(win_ptr),
+            &mut root,
+            &mut child,
+            &mut root_x,
+            &mut root_y,
+            &mut win_x,
+            &mut win_y,
+            &mut mask,
+        );
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+    use std::rc::Rc;
+
+    #[test]
+    fn callbacks_free_what_their_closure_captured() {
+        let state = Rc::new(());
+        let captured = state.clone();
+        let callback = Callback::new(move || drop(captured.clone()));
+        assert_eq!(Rc::strong_count(&state), 2);
+        drop(callback);
+        assert_eq!(Rc::strong_count(&state), 1);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn key_event_fields_are_at_their_xlib_offsets() {
+        assert_eq!(mem::offset_of!(XKeyEvent, window), 32);
+        assert_eq!(mem::offset_of!(XKeyEvent, state), 80);
+        assert_eq!(mem::offset_of!(XKeyEvent, keycode), 84);
+    }
+
+    #[test]
+    fn key_states_are_taken_in_order_for_each_window() {
+        let (shift, ctrl) = (1 << 0, 1 << 2);
+        push_key_state(1, shift);
+        push_key_state(2, 0);
+        push_key_state(1, ctrl);
+        assert_eq!(take_key_state(1), Some(shift));
+        // the state of the window 2 was recorded before, it had no hook to take it
+        assert_eq!(take_key_state(1), Some(ctrl));
+        assert_eq!(take_key_state(1), None);
+
+        // states never taken are all dropped once there are too many
+        for _ in 0..MAX_KEY_STATES {
+            push_key_state(2, 0);
+        }
+        push_key_state(1, shift);
+        assert_eq!(take_key_state(2), None);
+    }
 }
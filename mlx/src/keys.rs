@@ -0,0 +1,156 @@
+//! Names of the common keys, for the keycodes given to the key hooks.
+//!
+//! The keycodes are X11 keysyms, the `xev` command prints them for the other keys.
+//!
+//! Usage:
+//!```
+//! use minilibx::Key;
+//!
+//! window.key_hook(
+//!     |keycode, _| match Key::from_keycode(keycode) {
+//!         Some(Key::Escape | Key::Q) => process::exit(0),
+//!         Some(Key::Enter) => println!("enter"),
+//!         _ => (),
+//!     },
+//!     &(),
+//! );
+//!```
+
+macro_rules! keys {
+    ($($(#[$doc:meta])* $name:ident = $keysym:literal,)*) => {
+        /// A key, with its X11 keysym as value.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[repr(i32)]
+        pub enum Key {
+            $($(#[$doc])* $name = $keysym,)*
+        }
+
+        impl Key {
+            /// Key of a keycode given to the key hooks, `None` for the keys without a name here.
+            pub fn from_keycode(keycode: i32) -> Option<Self> {
+                match keycode {
+                    $($keysym => Some(Self::$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+keys! {
+    /// Escape
+    Escape = 65307,
+    /// Enter, or Return
+    Enter = 65293,
+    /// Tab
+    Tab = 65289,
+    /// Backspace
+    Backspace = 65288,
+    /// Space bar
+    Space = 32,
+    /// Left arrow
+    Left = 65361,
+    /// Up arrow
+    Up = 65362,
+    /// Right arrow
+    Right = 65363,
+    /// Down arrow
+    Down = 65364,
+    /// `+`
+    Plus = 43,
+    /// `,`
+    Comma = 44,
+    /// `-`
+    Minus = 45,
+    /// `.`
+    Period = 46,
+    /// `=`
+    Equal = 61,
+    /// `[`
+    BracketLeft = 91,
+    /// `]`
+    BracketRight = 93,
+    /// `+` of the keypad
+    KeypadAdd = 65451,
+    /// `-` of the keypad
+    KeypadSubtract = 65453,
+    /// `0` of the number row
+    Num0 = 48,
+    /// `1` of the number row
+    Num1 = 49,
+    /// `2` of the number row
+    Num2 = 50,
+    /// `3` of the number row
+    Num3 = 51,
+    /// `4` of the number row
+    Num4 = 52,
+    /// `5` of the number row
+    Num5 = 53,
+    /// `6` of the number row
+    Num6 = 54,
+    /// `7` of the number row
+    Num7 = 55,
+    /// `8` of the number row
+    Num8 = 56,
+    /// `9` of the number row
+    Num9 = 57,
+    /// `a`
+    A = 97,
+    /// `b`
+    B = 98,
+    /// `c`
+    C = 99,
+    /// `d`
+    D = 100,
+    /// `e`
+    E = 101,
+    /// `f`
+    F = 102,
+    /// `g`
+    G = 103,
+    /// `h`
+    H = 104,
+    /// `i`
+    I = 105,
+    /// `j`
+    J = 106,
+    /// `k`
+    K = 107,
+    /// `l`
+    L = 108,
+    /// `m`
+    M = 109,
+    /// `n`
+    N = 110,
+    /// `o`
+    O = 111,
+    /// `p`
+    P = 112,
+    /// `q`
+    Q = 113,
+    /// `r`
+    R = 114,
+    /// `s`
+    S = 115,
+    /// `t`
+    T = 116,
+    /// `u`
+    U = 117,
+    /// `v`
+    V = 118,
+    /// `w`
+    W = 119,
+    /// `x`
+    X = 120,
+    /// `y`
+    Y = 121,
+    /// `z`
+    Z = 122,
+}
+
+impl Key {
+    /// Keycode of the key, as given to the key hooks.
+    pub const fn keycode(self) -> i32 {
+        self as i32
+    }
+}
@@ -13,63 +13,171 @@
 ```rust
 extern crate minilibx;
 
-use std::process;
-use minilibx::{Mlx, MlxError};
+use std::rc::Rc;
+use minilibx::{Key, Mlx};
 
 fn main() {
     let mlx = Mlx::new().unwrap();
 
     let width = 1080;
     let height = 720;
-    let window = mlx.new_window(width, height, "Mlx example").unwrap();
+    // shared with the hook, which draws into it
+    let window = Rc::new(mlx.new_window(width, height, "Mlx example").unwrap());
 
     let image = match mlx.new_image(width, height) {
         Ok(img) => img,
-        Err(e) => match e {
-            MlxError::Any(s) => return println!("{}", s),
-            _ => return,
-        },
+        Err(e) => return eprintln!("{}", e),
     };
 
     println!("{}, {}", image.size_line, image.bits_per_pixel);
 
+    // the close button of the window manager
+    let hook_mlx = mlx.clone();
+    window.close_hook(move |_| hook_mlx.loop_end(), &());
+
+    let (hook_mlx, hook_window) = (mlx.clone(), window.clone());
     window.key_hook(
         move |keycode, _| {
             // you can also check keycodes using the `xev` command
             println!("{}", keycode);
 
-            // `q`
-            if keycode == 113 {
-                process::exit(0);
-            // Enter
-            } else if keycode == 97 {
-                let x = width / 2;
-                let y = height / 2;
-                let color = 0xffffff;
-                for i in 0..50 {
-                    mlx.pixel_put(&window, x + i, y + i, color);
+            match Key::from_keycode(keycode) {
+                Some(Key::Q) => hook_mlx.loop_end(),
+                Some(Key::Enter) => {
+                    let x = width / 2;
+                    let y = height / 2;
+                    let color = 0xffffff;
+                    for i in 0..50 {
+                        hook_mlx.pixel_put(&hook_window, x + i, y + i, color);
+                    }
                 }
+                _ => (),
             }
         },
         &(),
     );
 
-    // this will loop forever
-    mlx.event_loop();
+    // runs until loop_end is called, then drops the hooks with the window they captured
+    if let Err(e) = mlx.event_loop() {
+        eprintln!("{}", e);
+    }
 }
 ```
+
+# Ownership
+
+Windows and images are freed when they are dropped, so they cannot be copied:
+share them with `Rc` to use them in the hooks, like the window above.
+[Mlx](struct.Mlx.html) is a cheap handle to the connection to the X server, which can be cloned,
+and which stays open until the last handle, window and image are dropped.
+The `destroy_*` methods take their resource by value and are the same as dropping it.
+
+The hooks take closures which are kept until the event loop returns, so they only need the `'static`
+bound for what they capture: move an `Rc<RefCell<State>>` into them to change shared state,
+and give `&()` as the data argument, which is only kept for compatibility.
+The closures are dropped when the loop returns, with what they captured,
+so the program can return from `main` once [loop_end](struct.Mlx.html#method.loop_end) is called and free everything.
 */
 
+use std::cell::RefCell;
 use std::ffi::c_void;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::ops::Range;
+use std::rc::{Rc, Weak};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
+pub mod colors;
 mod ffi;
+mod font;
+pub mod keys;
+#[cfg(feature = "widgets")]
+pub mod widgets;
+
+pub use colors::Color;
+pub use ffi::{MlxError, XEventData};
+pub use keys::Key;
+
+/// Distance between the baselines of two lines of text, in pixels.
+pub const LINE_HEIGHT: i32 = 14;
+
+/// Directions in which the outline of a string is drawn.
+const OUTLINE_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Connection to the X server, closed once the last [Mlx], window and image using it are dropped.
+struct Display {
+    mlx_ptr: *mut c_void,
+    /// closures of the hooks, freed when the event loop returns
+    hooks: RefCell<Vec<(HookSlot, ffi::Callback)>>,
+    /// condition checked by the loop hook, set by [Mlx::event_loop_until]
+    should_exit: RefCell<Option<Box<dyn FnMut() -> bool>>>,
+}
+
+/// What a hook closure was registered for, to remove the hook before freeing it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HookSlot {
+    Loop,
+    /// window and X event of the hook
+    Window(*mut c_void, i32),
+    /// hook of a destroyed window, which cannot run anymore
+    Closed,
+}
+
+impl Display {
+    fn keep(&self, slot: HookSlot, callback: ffi::Callback) {
+        self.hooks.borrow_mut().push((slot, callback));
+    }
 
-pub use ffi::MlxError;
+    /// Removes the hooks and frees their closures, with what they captured.
+    fn release_hooks(&self) {
+        // taken out first: dropping a closure may drop a window, which marks its hooks
+        let hooks = mem::take(&mut *self.hooks.borrow_mut());
+        for (slot, _) in &hooks {
+            match *slot {
+                HookSlot::Loop => ffi::unhook_loop(self.mlx_ptr),
+                HookSlot::Window(win_ptr, x_event) => ffi::unhook(win_ptr, x_event),
+                HookSlot::Closed => (),
+            }
+        }
+        drop(hooks);
+    }
+
+    /// Ends the event loop once `should_exit` returns true.
+    fn check_exit(&self) {
+        let mut should_exit = self.should_exit.borrow_mut();
+        if should_exit
+            .as_mut()
+            .is_some_and(|should_exit| should_exit())
+        {
+            ffi::loop_end(self.mlx_ptr);
+        }
+    }
+}
+
+impl Drop for Display {
+    fn drop(&mut self) {
+        ffi::destroy(self.mlx_ptr);
+    }
+}
 
 /// Api method holder.
-#[derive(Clone, Copy)]
+///
+/// Clones share the same connection to the X server, see [the ownership rules](index.html#ownership).
+#[derive(Clone)]
 pub struct Mlx {
-    mlx_ptr: *mut c_void,
+    display: Rc<Display>,
 }
 
 impl Mlx {
@@ -82,37 +190,91 @@ impl Mlx {
     ///let mlx = Mlx::new().unwrap();
     ///```
     pub fn new() -> Result<Self, MlxError> {
+        let mlx_ptr = ffi::init()?;
         Ok(Self {
-            mlx_ptr: ffi::init()?,
+            display: Rc::new(Display {
+                mlx_ptr,
+                hooks: RefCell::new(Vec::new()),
+                should_exit: RefCell::new(None),
+            }),
         })
     }
 
     /// Creates a new [window](struct.MlxWindow.html) instance.
     ///
+    /// The size must be positive, an [MlxError::Any](enum.MlxError.html#variant.Any) is returned otherwise.
+    ///
     /// Usage:
     ///```
     /// let image = mlx.new_window(1920, 1080, "mlx-example").unwrap();
     ///```
     pub fn new_window(&self, size_x: i32, size_y: i32, title: &str) -> Result<MlxWindow, MlxError> {
+        let win_ptr = ffi::new_window(self.ptr(), size_x, size_y, title)?;
         Ok(MlxWindow {
-            win_ptr: ffi::new_window(self.mlx_ptr, size_x, size_y, title)?,
+            display: self.display.clone(),
+            win_ptr,
         })
     }
 
+    /// Creates a new [window](struct.MlxWindow.html) which the user can resize.
+    ///
+    /// The minilibx asks the window manager to keep its windows at their initial size, this lifts that restriction.
+    /// The images are not resized along: use a [resize_hook](struct.MlxWindow.html#method.resize_hook)
+    /// to create new ones of the new size.
+    ///
+    /// Usage:
+    ///```
+    /// let window = mlx.new_window_resizable(1080, 720, "mlx-example").unwrap();
+    ///```
+    pub fn new_window_resizable(
+        &self,
+        size_x: i32,
+        size_y: i32,
+        title: &str,
+    ) -> Result<MlxWindow, MlxError> {
+        let window = self.new_window(size_x, size_y, title)?;
+        ffi::allow_resize(self.ptr(), window.ptr());
+        Ok(window)
+    }
+
     /// Clears the window with black.
     pub fn clear_window(&self, window: &MlxWindow) {
-        ffi::clear_window(self.mlx_ptr, window.win_ptr);
+        ffi::clear_window(self.ptr(), window.ptr());
     }
 
-    /// Destroys the window. This function also drops the window object.
-    pub fn destroy_window(&self, window: &MlxWindow) {
-        ffi::destroy_window(self.mlx_ptr, window.win_ptr);
+    /// Destroys the window, which is the same as dropping it.
+    pub fn destroy_window(&self, window: MlxWindow) {
         drop(window);
     }
 
+    /// Changes the title of the window.
+    ///
+    /// The minilibx can only set the title when creating the window, so this sets the X11 `WM_NAME` property directly.
+    ///
+    /// Usage:
+    ///```
+    /// mlx.set_window_title(&window, "mlx-example (paused)").unwrap();
+    ///```
+    pub fn set_window_title(&self, window: &MlxWindow, title: &str) -> Result<(), MlxError> {
+        ffi::set_window_title(self.ptr(), window.ptr(), title)
+    }
+
+    /// Moves the top left corner of the window to `(x, y)` on the screen.
+    ///
+    /// The window manager can still decide to place the window elsewhere.
+    ///
+    /// Usage:
+    ///```
+    /// let (screen_width, screen_height) = mlx.get_screen_size();
+    /// mlx.move_window(&window, (screen_width - 1080) / 2, (screen_height - 720) / 2);
+    ///```
+    pub fn move_window(&self, window: &MlxWindow, x: i32, y: i32) {
+        ffi::move_window(self.ptr(), window.ptr(), x, y);
+    }
+
     /// Get the actual screen size.
     pub fn get_screen_size(&self) -> (i32, i32) {
-        ffi::get_screen_size(self.mlx_ptr)
+        ffi::get_screen_size(self.ptr())
     }
 
     /// Put a pixel on the screen
@@ -128,13 +290,15 @@ impl Mlx {
     /// let color = 0x0000ff; // blue
     /// mlx.pixel_put(&window, x, y, color);
     ///```
-    pub fn pixel_put(&self, window: &MlxWindow, x: i32, y: i32, color: i32) {
-        ffi::pixel_put(self.mlx_ptr, window.win_ptr, x, y, color);
+    pub fn pixel_put(&self, window: &MlxWindow, x: i32, y: i32, color: impl Into<Color>) {
+        ffi::pixel_put(self.ptr(), window.ptr(), x, y, color.into().as_i32());
     }
 
     /// Writes a string on the screen
     ///
     /// Color is encoded in rgb as well.
+    ///
+    /// The string cannot contain NUL bytes, an [MlxError::Nul](enum.MlxError.html#variant.Nul) is returned otherwise.
     /// Usage:
     ///```
     /// let x = 200;
@@ -147,16 +311,121 @@ impl Mlx {
         window: &MlxWindow,
         x: i32,
         y: i32,
-        color: i32,
+        color: impl Into<Color>,
+        s: &str,
+    ) -> Result<(), MlxError> {
+        ffi::string_put(self.ptr(), window.ptr(), x, y, color.into().as_i32(), s)
+    }
+
+    /// Writes a string on the screen, truncating it at the first NUL byte.
+    ///
+    /// Unlike [string_put](#method.string_put), this never fails: the string is cut at its first NUL byte and a warning is printed on stderr.
+    /// Useful for strings coming from the user.
+    pub fn string_put_truncated(
+        &self,
+        window: &MlxWindow,
+        x: i32,
+        y: i32,
+        color: impl Into<Color>,
+        s: &str,
+    ) {
+        let s = match s.find('\0') {
+            Some(offset) => {
+                eprintln!("warning: string truncated at NUL byte {}", offset);
+                &s[..offset]
+            }
+            None => s,
+        };
+        // the string has no NUL byte left, this cannot fail
+        let _ = ffi::string_put(self.ptr(), window.ptr(), x, y, color.into().as_i32(), s);
+    }
+
+    /// Writes a string on several lines, one for each `\n`, spaced by [LINE_HEIGHT](constant.LINE_HEIGHT.html).
+    ///
+    /// Usage:
+    ///```
+    /// let color = 0xffffff; // white
+    /// mlx.string_put_multiline(&window, 10, 20, color, "first line\nsecond line");
+    ///```
+    pub fn string_put_multiline(
+        &self,
+        window: &MlxWindow,
+        x: i32,
+        y: i32,
+        color: impl Into<Color>,
+        s: &str,
+    ) -> Result<(), MlxError> {
+        let color = color.into();
+        for (i, line) in s.lines().enumerate() {
+            self.string_put(window, x, y + LINE_HEIGHT * i as i32, color, line)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a string surrounded by a one pixel outline, readable on any background.
+    ///
+    /// The string can have several lines, like with [string_put_multiline](#method.string_put_multiline).
+    ///
+    /// Usage:
+    ///```
+    /// let color = 0xffffff; // white
+    /// let outline = 0x000000; // black
+    /// mlx.string_put_outlined(&window, 10, 20, color, outline, "Hello World");
+    ///```
+    pub fn string_put_outlined(
+        &self,
+        window: &MlxWindow,
+        x: i32,
+        y: i32,
+        color: impl Into<Color>,
+        outline: impl Into<Color>,
         s: &str,
     ) -> Result<(), MlxError> {
-        ffi::string_put(self.mlx_ptr, window.win_ptr, x, y, color, s)
+        let outline = outline.into();
+        for (dx, dy) in OUTLINE_OFFSETS {
+            self.string_put_multiline(window, x + dx, y + dy, outline, s)?;
+        }
+        self.string_put_multiline(window, x, y, color, s)
+    }
+
+    /// Writes a line of text on the screen `scale` times larger than its base size, with `y` as the baseline.
+    ///
+    /// The text uses a built-in 5x7 font, a character being `6 * scale` pixels wide, rather than the X font of
+    /// [string_put](#method.string_put), whose glyphs cannot be scaled. Like it, only the pixels of the text are drawn.
+    /// A scale below 1 is the same as 1, and the characters outside of printable ASCII are drawn as `?`.
+    ///
+    /// Usage:
+    ///```
+    /// mlx.string_put_scaled(&window, 10, 40, colors::WHITE, 3, "zoom x1024");
+    ///```
+    pub fn string_put_scaled(
+        &self,
+        window: &MlxWindow,
+        x: i32,
+        y: i32,
+        color: impl Into<Color>,
+        scale: i32,
+        s: &str,
+    ) {
+        let color = color.into().as_i32();
+        let scale = scale.max(1);
+        let top = y - font::GLYPH_HEIGHT * scale;
+        for (px, py) in font::pixels(s) {
+            let (left, top) = (x + px * scale, top + py * scale);
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    ffi::pixel_put(self.ptr(), window.ptr(), left + dx, top + dy, color);
+                }
+            }
+        }
     }
 
     /// Creates a new [image](struct.MlxImage.html).
+    ///
+    /// The size must be positive, an [MlxError::Any](enum.MlxError.html#variant.Any) is returned otherwise.
     pub fn new_image(&self, width: i32, height: i32) -> Result<MlxImage, MlxError> {
-        let ptr = ffi::new_image(self.mlx_ptr, width, height)?;
-        let image = MlxImage::new(ptr, width, height)?;
+        let ptr = ffi::new_image(self.ptr(), width, height)?;
+        let image = MlxImage::new(&self.display, ptr, width, height)?;
         Ok(image)
     }
 
@@ -166,22 +435,132 @@ impl Mlx {
     ///
     /// It however handles transparency.
     pub fn xpm_to_image(&self, xpm_data: Vec<String>) -> Result<MlxImage, MlxError> {
-        let data = ffi::xpm_to_image(self.mlx_ptr, xpm_data)?;
-        let image = MlxImage::new(data.ptr, data.width, data.height)?;
+        let data = ffi::xpm_to_image(self.ptr(), xpm_data)?;
+        let image = MlxImage::new(&self.display, data.ptr, data.width, data.height)?;
         Ok(image)
     }
 
-
     /// Creates a new [image](struct.MlxImage.html) from an [xpm](https://en.wikipedia.org/wiki/X_PixMap) file.
     pub fn xpm_file_to_image(&self, filename: &str) -> Result<MlxImage, MlxError> {
-        let data = ffi::xpm_file_to_image(self.mlx_ptr, filename)?;
-        let image = MlxImage::new(data.ptr, data.width, data.height)?;
+        let data = ffi::xpm_file_to_image(self.ptr(), filename)?;
+        let image = MlxImage::new(&self.display, data.ptr, data.width, data.height)?;
+        Ok(image)
+    }
+
+    /// Creates a new [image](struct.MlxImage.html) from a PNG file, enabled with the `png` feature.
+    ///
+    /// The minilibx images have no transparency, so the transparent pixels are blended over black,
+    /// see [png_to_image_over](#method.png_to_image_over) for another background.
+    ///
+    /// Usage:
+    ///```
+    /// let sprite = mlx.png_to_image("sprite.png")?;
+    /// mlx.put_image_to_window(&window, &sprite, 0, 0);
+    ///```
+    #[cfg(feature = "png")]
+    pub fn png_to_image(&self, path: &str) -> Result<MlxImage, MlxError> {
+        self.png_to_image_over(path, colors::BLACK)
+    }
+
+    /// Creates a new [image](struct.MlxImage.html) from a PNG file, blending the transparent pixels over `background`.
+    ///
+    /// Any PNG color type and depth is read, the palettes are expanded and the 16 bits channels cut down to 8 bits.
+    /// Errors opening or decoding the file are returned as [MlxError::Any](enum.MlxError.html#variant.Any),
+    /// with the path and the cause.
+    #[cfg(feature = "png")]
+    pub fn png_to_image_over(
+        &self,
+        path: &str,
+        background: impl Into<Color>,
+    ) -> Result<MlxImage, MlxError> {
+        let error = |e: &dyn std::fmt::Display| MlxError::Any(format!("{}: {}", path, e));
+        let file = File::open(path).map_err(|e| error(&e))?;
+        let mut decoder = png::Decoder::new(io::BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| error(&e))?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).map_err(|e| error(&e))?;
+
+        let image = self.new_image(info.width as i32, info.height as i32)?;
+        let background = background.into().as_u32();
+        let channels = info.color_type.samples();
+        for (y, line) in data
+            .chunks(info.line_size)
+            .take(info.height as usize)
+            .enumerate()
+        {
+            for (x, pixel) in line.chunks(channels).take(info.width as usize).enumerate() {
+                // gray and gray with alpha have a single color channel
+                let (rgb, alpha) = match *pixel {
+                    [r, g, b, a] => ([r, g, b], a),
+                    [r, g, b] => ([r, g, b], 255),
+                    [v, a] => ([v, v, v], a),
+                    [v] => ([v, v, v], 255),
+                    _ => return Err(error(&"unsupported pixel format")),
+                };
+                let blend = |channel: u8, shift: u32| {
+                    let back = background >> shift & 0xff;
+                    let (channel, alpha) = (channel as u32, alpha as u32);
+                    ((channel * alpha + back * (255 - alpha) + 127) / 255) as u8
+                };
+                let color = Color::rgb(blend(rgb[0], 16), blend(rgb[1], 8), blend(rgb[2], 0));
+                image.pixel_put(x as i32, y as i32, color);
+            }
+        }
+        Ok(image)
+    }
+
+    /// Creates a new [image](struct.MlxImage.html) from `width * height` pixels of 4 bytes, red, green, blue and alpha,
+    /// row after row with no padding.
+    ///
+    /// Each row is converted to the depth and [endian](struct.MlxImage.html#structfield.endian) order of the image,
+    /// then copied in at once, the padding up to [size_line](struct.MlxImage.html#structfield.size_line) is left alone.
+    /// The alpha is dropped, the minilibx images have no transparency.
+    /// An [MlxError::Any](enum.MlxError.html#variant.Any) is returned when `data` is not `width * height * 4` bytes long.
+    ///
+    /// Usage:
+    ///```
+    /// let pixels: Vec<u8> = render_rgba(320, 200);
+    /// let image = mlx.image_from_rgba(320, 200, &pixels)?;
+    /// mlx.put_image_to_window(&window, &image, 0, 0);
+    ///```
+    pub fn image_from_rgba(
+        &self,
+        width: i32,
+        height: i32,
+        data: &[u8],
+    ) -> Result<MlxImage, MlxError> {
+        let expected = width.max(0) as usize * height.max(0) as usize * 4;
+        if data.len() != expected {
+            return Err(MlxError::Any(format!(
+                "Invalid RGBA data of {} bytes for a {}x{} image, expected {}.",
+                data.len(),
+                width,
+                height,
+                expected
+            )));
+        }
+        // the size is checked here, so the rows below are not empty
+        let image = self.new_image(width, height)?;
+
+        let bytes_per_pixel = image.bytes_per_pixel();
+        let mut row = Vec::with_capacity(width as usize * bytes_per_pixel);
+        for (y, line) in data.chunks_exact(width as usize * 4).enumerate() {
+            row.clear();
+            for pixel in line.chunks_exact(4) {
+                let bytes = image.color_bytes(Color::rgb(pixel[0], pixel[1], pixel[2]));
+                row.extend_from_slice(&bytes[..bytes_per_pixel]);
+            }
+            unsafe {
+                let start = image.area_start.add(y * image.size_line as usize) as *mut u8;
+                std::ptr::copy_nonoverlapping(row.as_ptr(), start, row.len());
+            }
+        }
         Ok(image)
     }
 
-    /// Destroy the image. Also drops the image instance.
-    pub fn destroy_image(&self, image: &MlxImage) {
-        ffi::destroy_image(self.mlx_ptr, image.img_ptr);
+    /// Destroys the image, which is the same as dropping it.
+    pub fn destroy_image(&self, image: MlxImage) {
         drop(image);
     }
 
@@ -194,7 +573,98 @@ impl Mlx {
     /// mlx.put_image_to_window(&window, &image, x, y);
     ///```
     pub fn put_image_to_window(&self, window: &MlxWindow, image: &MlxImage, x: i32, y: i32) {
-        ffi::put_image_to_window(self.mlx_ptr, window.win_ptr, image.img_ptr, x, y);
+        ffi::put_image_to_window(self.ptr(), window.ptr(), image.ptr(), x, y);
+    }
+
+    /// Draws an image to the window, skipping the draw when the image is fully outside of it.
+    ///
+    /// The minilibx passes the offsets as is to `XPutImage`, and the X server clips the image to the window:
+    /// negative offsets and images extending past the right or bottom edge are drawn partially, without artifacts.
+    /// An image fully outside of the window is still sent to the server for nothing, which this guards against
+    /// by checking the destination rectangle against the current window size (one round trip to the server).
+    ///
+    /// Returns whether the image was drawn.
+    ///
+    /// Usage:
+    ///```
+    /// // scrolls a minimap that is larger than the window
+    /// mlx.put_image_clipped(&window, &minimap, -scroll_x, -scroll_y);
+    ///```
+    pub fn put_image_clipped(&self, window: &MlxWindow, image: &MlxImage, x: i32, y: i32) -> bool {
+        let (_, _, width, height) = ffi::window_geometry(self.ptr(), window.ptr());
+        let outside = x >= width || y >= height || x + image.width <= 0 || y + image.height <= 0;
+        if !outside {
+            ffi::put_image_to_window(self.ptr(), window.ptr(), image.ptr(), x, y);
+        }
+        !outside
+    }
+
+    /// Draws an image to the window and waits for the X server to be done with it.
+    ///
+    /// When the MIT-SHM extension is available, the minilibx shares the image memory with the X server
+    /// and [put_image_to_window](#method.put_image_to_window) returns before the server has read it.
+    /// Writing the next frame into the image right away can then tear the displayed one.
+    /// This waits for the server (`XSync`) so the image can safely be reused, at the cost of a round trip.
+    /// Without the extension, the image is copied with the request and the wait is harmless.
+    ///
+    /// The minilibx does not give access to the double buffering extensions, so this cannot sync with the screen refresh.
+    pub fn present_synced(&self, window: &MlxWindow, image: &MlxImage, x: i32, y: i32) {
+        ffi::put_image_to_window(self.ptr(), window.ptr(), image.ptr(), x, y);
+        ffi::do_sync(self.ptr());
+    }
+
+    /// Sends the pending drawing requests to the X server and waits for it to process them (`XSync`).
+    ///
+    /// Xlib buffers the requests, so pixels and strings drawn from a one-shot handler (e.g. a key hook)
+    /// may not show up until the next event cycle. Call this after drawing to see the result right away.
+    /// This is not needed from a [loop_hook](#method.loop_hook), the event loop flushes the buffer on every iteration.
+    ///
+    /// Usage:
+    ///```
+    /// mlx.pixel_put(&window, 10, 10, 0xffffff);
+    /// mlx.sync();
+    ///```
+    pub fn sync(&self) {
+        ffi::do_sync(self.ptr());
+    }
+
+    /// Whether events were received and wait for the event loop to run their hooks (`XPending`).
+    ///
+    /// A long computation in a [loop_hook](#method.loop_hook) can check this to return early,
+    /// so the hooks of the input run without waiting for it to end.
+    ///
+    /// Usage:
+    ///```
+    /// mlx.loop_hook(move |_| {
+    ///     while !mlx.events_pending() {
+    ///         // a slice of the computation
+    ///     }
+    /// }, &());
+    ///```
+    pub fn events_pending(&self) -> bool {
+        ffi::events_pending(self.ptr())
+    }
+
+    /// Reads back what the window shows, overlays and strings included, into a new [image](struct.MlxImage.html).
+    ///
+    /// Unlike the images drawn to the window, this is exactly what the user sees, as a screenshot would.
+    /// The window must be mapped (visible on the screen).
+    /// The parts of the window outside of the screen are black, and the parts covered by other windows
+    /// are undefined: X only keeps the pixels of the visible regions, unless the server uses backing store.
+    ///
+    /// Usage:
+    ///```
+    /// let screenshot = mlx.capture_window(&window)?;
+    /// let top_left = screenshot.read_from(0);
+    ///```
+    pub fn capture_window(&self, window: &MlxWindow) -> Result<MlxImage, MlxError> {
+        let capture = ffi::capture_window(self.ptr(), window.ptr())?;
+        let image = self.new_image(capture.width, capture.height)?;
+        for (i, &pixel) in capture.pixels.iter().enumerate() {
+            let (x, y) = (i as i32 % capture.width, i as i32 / capture.width);
+            image.pixel_put(x, y, pixel);
+        }
+        Ok(image)
     }
 
     /// Transforms an RGB color parameter into a u32 value.
@@ -203,39 +673,108 @@ impl Mlx {
     ///
     /// You can use this to write into an [image](struct.MlxImage.html)
     pub fn get_color_value(&self, color: i32) -> u32 {
-        ffi::get_color_value(self.mlx_ptr, color)
+        ffi::get_color_value(self.ptr(), color)
     }
 
     /// Enables key autorepeat when pressing a key
     pub fn do_key_autorepeaton(&self) {
-        ffi::do_key_autorepeaton(self.mlx_ptr)
+        ffi::do_key_autorepeaton(self.ptr())
     }
 
     /// Disables key autorepeat when pressing a key
     pub fn do_key_autorepeatoff(&self) {
-        ffi::do_key_autorepeatoff(self.mlx_ptr)
+        ffi::do_key_autorepeatoff(self.ptr())
     }
 
     /// Moves the mouse cursor
     pub fn mouse_move(&self, window: &MlxWindow, x: i32, y: i32) {
-        ffi::mouse_move(self.mlx_ptr, window.win_ptr, x, y);
+        ffi::mouse_move(self.ptr(), window.ptr(), x, y);
     }
 
     /// Shows the mouse cursor
     pub fn mouse_show(&self, window: &MlxWindow) {
-        ffi::mouse_show(self.mlx_ptr, window.win_ptr);
+        ffi::mouse_show(self.ptr(), window.ptr());
     }
 
     /// Hides the mouse cursor
     pub fn mouse_hide(&self, window: &MlxWindow) {
-        ffi::mouse_hide(self.mlx_ptr, window.win_ptr);
+        ffi::mouse_hide(self.ptr(), window.ptr());
     }
 
     /// Run the event loop.
     ///
-    /// This is running an infinite loop which launches [hooks](struct.MlxWindow.html) when receiving events.
-    pub fn event_loop(&self) {
-        ffi::event_loop(self.mlx_ptr);
+    /// This is running a loop which launches [hooks](struct.MlxWindow.html) when receiving events,
+    /// until [loop_end](#method.loop_end) is called or the last window is destroyed.
+    ///
+    /// The hooks are removed when it returns, and their closures dropped with what they captured:
+    /// they must be registered again to run the loop again.
+    ///
+    /// A panic in a hook aborts the process, see [MlxWindow](struct.MlxWindow.html).
+    ///
+    /// Usage:
+    ///```
+    /// if let Err(e) = mlx.event_loop() {
+    ///     eprintln!("{}", e);
+    /// }
+    ///```
+    pub fn event_loop(&self) -> Result<(), MlxError> {
+        let status = ffi::event_loop(self.ptr());
+        self.display.release_hooks();
+        match status {
+            0 => Ok(()),
+            status => Err(MlxError::Any(format!(
+                "mlx_loop failed with status {}",
+                status
+            ))),
+        }
+    }
+
+    /// Makes the [event loop](#method.event_loop) return once the running hook returns (`mlx_loop_end`).
+    ///
+    /// The windows, images and [Mlx] handles captured by the hooks are dropped when the loop returns,
+    /// so returning from `main` afterwards frees everything, unlike `process::exit`.
+    ///
+    /// Usage:
+    ///```
+    /// let hook_mlx = mlx.clone();
+    /// window.close_hook(move |_| hook_mlx.loop_end(), &());
+    /// mlx.event_loop().unwrap();
+    /// // the window was closed
+    ///```
+    pub fn loop_end(&self) {
+        ffi::loop_end(self.ptr());
+    }
+
+    /// Runs the [event loop](#method.event_loop) until `should_exit` returns true,
+    /// checking it whenever no event occurs.
+    ///
+    /// It is checked after the [loop hook](#method.loop_hook), which keeps running.
+    ///
+    /// Usage:
+    ///```
+    /// let done = Rc::new(Cell::new(false));
+    /// let hook_done = done.clone();
+    /// window.close_hook(move |_| hook_done.set(true), &());
+    /// mlx.event_loop_until(move || done.get()).unwrap();
+    ///```
+    pub fn event_loop_until<F>(&self, should_exit: F) -> Result<(), MlxError>
+    where
+        F: FnMut() -> bool + 'static,
+    {
+        *self.display.should_exit.borrow_mut() = Some(Box::new(should_exit));
+        let hooked = self
+            .display
+            .hooks
+            .borrow()
+            .iter()
+            .any(|(slot, _)| *slot == HookSlot::Loop);
+        if !hooked {
+            // only checks the condition
+            self.set_loop_hook(|| ());
+        }
+        let result = self.event_loop();
+        self.display.should_exit.borrow_mut().take();
+        result
     }
 
     /// Hook running when no event occurs.
@@ -253,16 +792,58 @@ impl Mlx {
     where
         F: FnMut(&'static Args) + 'static,
     {
-        ffi::loop_hook(self.mlx_ptr, move || {
-            cb(args);
+        self.set_loop_hook(move || cb(args));
+    }
+
+    /// Hook running when no event occurs, given the time elapsed since its previous run.
+    ///
+    /// F should be a closure taking the elapsed time and the data you pass as an argument.
+    /// Moving things by their speed times the elapsed time keeps animations at the same pace whatever the frame rate.
+    /// There is no previous run on the first one, which is given a zero duration.
+    ///
+    /// Usage:
+    /// ```
+    /// let mut angle = 0.0;
+    /// mlx.loop_hook_timed(move |elapsed, _| {
+    ///     // half a turn per second
+    ///     angle += std::f64::consts::PI * elapsed.as_secs_f64();
+    /// }, &());
+    /// ```
+    pub fn loop_hook_timed<F, Args>(&self, mut cb: F, args: &'static Args)
+    where
+        F: FnMut(Duration, &'static Args) + 'static,
+    {
+        let mut last: Option<Instant> = None;
+        self.set_loop_hook(move || {
+            let now = Instant::now();
+            let elapsed = last.map_or(Duration::ZERO, |last| now - last);
+            last = Some(now);
+            cb(elapsed, args);
+        });
+    }
+
+    /// Replaces the loop hook by `cb`, followed by the check of [event_loop_until](#method.event_loop_until).
+    fn set_loop_hook(&self, mut cb: impl FnMut() + 'static) {
+        // weak: the closure is kept by the display itself
+        let display: Weak<Display> = Rc::downgrade(&self.display);
+        let callback = ffi::loop_hook(self.ptr(), move || {
+            cb();
+            if let Some(display) = display.upgrade() {
+                display.check_exit();
+            }
         });
+        self.display.keep(HookSlot::Loop, callback);
     }
 
-    /// Destroys the Mlx instance.
+    /// Drops the Mlx instance.
     ///
-    /// This function also drops free the Mlx instance.
-    pub fn destroy(&self) {
-        ffi::destroy(self.mlx_ptr);
+    /// The connection to the X server is closed once its clones, windows and images are dropped as well.
+    pub fn destroy(self) {
+        drop(self);
+    }
+
+    fn ptr(&self) -> *mut c_void {
+        self.display.mlx_ptr
     }
 }
 
@@ -271,12 +852,39 @@ impl Mlx {
 /// With hooks, you can provide closures that will run when an event occurs.
 ///
 /// The [mlx.event_loop](struct.Mlx.html#method.event_loop) method should run for these hooks to be executed.
-#[derive(Clone, Copy)]
+///
+/// A panic in a hook cannot unwind through the C event loop: it is printed as usual, then the process is aborted.
+///
+/// The window is closed when it is dropped, and its hooks stop running.
+/// Their closures are freed with the others when the event loop returns, see [the ownership rules](index.html#ownership).
 pub struct MlxWindow {
+    display: Rc<Display>,
     win_ptr: *mut c_void,
 }
 
+impl Drop for MlxWindow {
+    fn drop(&mut self) {
+        ffi::destroy_window(self.display.mlx_ptr, self.win_ptr);
+        // its closures may be running, they are freed with the others when the loop returns
+        for (slot, _) in self.display.hooks.borrow_mut().iter_mut() {
+            if matches!(*slot, HookSlot::Window(win_ptr, _) if win_ptr == self.win_ptr) {
+                *slot = HookSlot::Closed;
+            }
+        }
+    }
+}
+
 impl MlxWindow {
+    fn ptr(&self) -> *mut c_void {
+        self.win_ptr
+    }
+
+    /// Keeps the closure of the hook of the `x_event` events until the event loop returns.
+    fn keep(&self, x_event: i32, callback: ffi::Callback) {
+        self.display
+            .keep(HookSlot::Window(self.win_ptr, x_event), callback);
+    }
+
     /// Hook running whenever a mouse event is received.
     ///
     /// F should be a closure taking 4 arguments: the buttons, x, y and the data you provide as last argument of the mouse_hook call.
@@ -292,90 +900,335 @@ impl MlxWindow {
     where
         F: FnMut(i32, i32, i32, &'static Args) + 'static,
     {
-        ffi::mouse_hook(self.win_ptr, move |buttons: i32, x: i32, y: i32| {
+        let callback = ffi::mouse_hook(self.ptr(), move |buttons: i32, x: i32, y: i32| {
             cb(buttons, x, y, args);
         });
+        self.keep(ffi::BUTTON_PRESS, callback);
     }
 
-    /// Hook running whenever a key event is received.
-    ///
-    /// F should be a closure taking 2 arguments: the keycode and the data you provide as last argument of the mouse_hook call.
+    /// Same as [mouse_hook](#method.mouse_hook), with the button as a [MouseButton](enum.MouseButton.html).
     ///
     /// Usage:
     /// ```
-    /// let arg = (2, 3);
-    /// window.key_hook(|keycode, args| {
-    ///     println!("{}, ({}, {})", keycode, args.0, args.1);
-    /// }, &arg);
+    /// window.mouse_hook_typed(|button, x, y, _| match button {
+    ///     MouseButton::WheelUp => println!("zoom in at {}, {}", x, y),
+    ///     MouseButton::WheelDown => println!("zoom out at {}, {}", x, y),
+    ///     _ => (),
+    /// }, &());
     /// ```
-    pub fn key_hook<F, Args>(&self, mut cb: F, args: &'static Args)
+    pub fn mouse_hook_typed<F, Args>(&self, mut cb: F, args: &'static Args)
     where
-        F: FnMut(i32, &'static Args) + 'static,
+        F: FnMut(MouseButton, i32, i32, &'static Args) + 'static,
     {
-        ffi::key_hook(self.win_ptr, move |keycode| {
-            cb(keycode, args);
-        });
+        self.mouse_hook(
+            move |button, x, y, args| cb(button.into(), x, y, args),
+            args,
+        );
     }
 
-    /// Hook running whenever an 'expose' event is received.
+    /// Hook running whenever a mouse button is released.
     ///
-    /// F should be a closure taking the data you pass as an argument.
+    /// F should be a closure taking 4 arguments: the button, x, y and the data you provide as last argument of the mouse_release_hook call.
     ///
     /// Usage:
     /// ```
     /// let arg = (2, 3);
-    /// window.expose_hook(|args| {
-    ///     println!("({}, {})", args.0, args.1);
+    /// window.mouse_release_hook(|button, x, y, args| {
+    ///     println!("{} released at {}, {}, ({}, {})", button, x, y, args.0, args.1);
     /// }, &arg);
     /// ```
-    ///
-    /// The expose hook is running whenever the window or part of it should be redrawn.
-    pub fn expose_hook<F, Args>(&self, mut cb: F, args: &'static Args)
+    pub fn mouse_release_hook<F, Args>(&self, mut cb: F, args: &'static Args)
     where
-        F: FnMut(&'static Args) + 'static,
+        F: FnMut(i32, i32, i32, &'static Args) + 'static,
     {
-        ffi::expose_hook(self.win_ptr, move || {
-            cb(args);
+        let (event, mask) = (ffi::BUTTON_RELEASE, ffi::BUTTON_RELEASE_MASK);
+        let callback = ffi::button_hook(self.ptr(), event, mask, move |button, x, y| {
+            cb(button, x, y, args);
         });
+        self.keep(event, callback);
     }
 
-    /// Hook running whenever the event you specify occurs.
+    /// Hook running whenever the mouse moves over the window.
     ///
-    /// F should be a closure taking the data you pass as an argument.
+    /// F should be a closure taking 3 arguments: x, y and the data you provide as last argument of the motion_hook call.
     ///
     /// Usage:
     /// ```
     /// let arg = (2, 3);
-    /// let x_event = 2; // keypress
-    /// let x_mask = 0; // no mask
-    /// window.hook(x_event, x_mask, |args| {
-    ///     println!("({}, {})", args.0, args.1);
+    /// window.motion_hook(|x, y, args| {
+    ///     println!("{}, {}, ({}, {})", x, y, args.0, args.1);
     /// }, &arg);
     /// ```
-    ///
-    /// You can find informations on x events in `/usr/include/X11/X.h` around line 180 and x event masks around line 150.
-    pub fn hook<F, Args>(&self, x_event: i32, x_mask: i32, mut cb: F, args: &'static Args)
+    pub fn motion_hook<F, Args>(&self, mut cb: F, args: &'static Args)
     where
-        F: FnMut(&'static Args) + 'static,
+        F: FnMut(i32, i32, &'static Args) + 'static,
     {
-        ffi::hook(self.win_ptr, x_event, x_mask, move || {
-            cb(args);
+        let callback = ffi::motion_hook(self.ptr(), move |x, y| {
+            cb(x, y, args);
         });
+        self.keep(ffi::MOTION_NOTIFY, callback);
     }
-}
 
-/// Enum describing the [endianness](https://en.wikipedia.org/wiki/Endianness) of some data.
-#[derive(Clone, Copy, Debug)]
-pub enum Endian {
-    /// little endian variant.
+    /// Hook running whenever a key event is received.
+    ///
+    /// F should be a closure taking 2 arguments: the keycode and the data you provide as last argument of the mouse_hook call.
+    ///
+    /// Usage:
+    /// ```
+    /// let arg = (2, 3);
+    /// window.key_hook(|keycode, args| {
+    ///     println!("{}, ({}, {})", keycode, args.0, args.1);
+    /// }, &arg);
+    /// ```
+    pub fn key_hook<F, Args>(&self, mut cb: F, args: &'static Args)
+    where
+        F: FnMut(i32, &'static Args) + 'static,
+    {
+        self.key_hook_fn(move |keycode| cb(keycode, args));
+    }
+
+    /// Same as [key_hook](#method.key_hook), without the data argument.
+    ///
+    /// The closure is kept until the event loop returns, so it can own the state it changes:
+    ///
+    /// Usage:
+    /// ```
+    /// let presses = Rc::new(RefCell::new(0));
+    /// let counter = presses.clone();
+    /// window.key_hook_fn(move |_| *counter.borrow_mut() += 1);
+    /// ```
+    pub fn key_hook_fn(&self, cb: impl FnMut(i32) + 'static) {
+        let callback = ffi::key_hook(self.ptr(), cb);
+        self.keep(ffi::KEY_RELEASE, callback);
+    }
+
+    /// Hook running whenever a key is pressed, with the state of the modifier keys.
+    ///
+    /// Unlike [key_hook](#method.key_hook), which runs when the key is released, this runs on the `KeyPress` event.
+    ///
+    /// F should be a closure taking 3 arguments: the keycode, the [modifiers](struct.Modifiers.html) held down and the data you provide as last argument of the key_press_hook call.
+    ///
+    /// Usage:
+    /// ```
+    /// let arg = (2, 3);
+    /// window.key_press_hook(|keycode, modifiers, args| {
+    ///     if modifiers.ctrl && Key::from_keycode(keycode) == Some(Key::S) {
+    ///         println!("ctrl+s, ({}, {})", args.0, args.1);
+    ///     }
+    /// }, &arg);
+    /// ```
+    ///
+    /// The minilibx only gives the keycode to the hook, so the state mask of each event is kept as Xlib reads it,
+    /// and decoded when the hook runs.
+    pub fn key_press_hook<F, Args>(&self, mut cb: F, args: &'static Args)
+    where
+        F: FnMut(i32, Modifiers, &'static Args) + 'static,
+    {
+        let callback =
+            ffi::key_press_hook(self.display.mlx_ptr, self.ptr(), move |keycode, state| {
+                cb(keycode, Modifiers::from_state(state), args);
+            });
+        self.keep(ffi::KEY_PRESS, callback);
+    }
+
+    /// Hook running whenever an 'expose' event is received.
+    ///
+    /// F should be a closure taking the data you pass as an argument.
+    ///
+    /// Usage:
+    /// ```
+    /// let arg = (2, 3);
+    /// window.expose_hook(|args| {
+    ///     println!("({}, {})", args.0, args.1);
+    /// }, &arg);
+    /// ```
+    ///
+    /// The expose hook is running whenever the window or part of it should be redrawn.
+    pub fn expose_hook<F, Args>(&self, mut cb: F, args: &'static Args)
+    where
+        F: FnMut(&'static Args) + 'static,
+    {
+        let callback = ffi::expose_hook(self.ptr(), move || {
+            cb(args);
+        });
+        self.keep(ffi::EXPOSE, callback);
+    }
+
+    /// Hook running when the window is closed, by the close button of the window manager.
+    ///
+    /// F should be a closure taking the data you pass as an argument.
+    /// Without this hook, the close button does nothing and the program keeps running.
+    ///
+    /// Usage:
+    /// ```
+    /// let hook_mlx = mlx.clone();
+    /// window.close_hook(move |_| hook_mlx.loop_end(), &());
+    /// ```
+    ///
+    /// The minilibx runs the hooks of the `DestroyNotify` event for the `WM_DELETE_WINDOW` message of the window manager.
+    pub fn close_hook<F, Args>(&self, mut cb: F, args: &'static Args)
+    where
+        F: FnMut(&'static Args) + 'static,
+    {
+        let callback = ffi::hook(
+            self.display.mlx_ptr,
+            self.ptr(),
+            ffi::DESTROY_NOTIFY,
+            0,
+            move |_| {
+                cb(args);
+            },
+        );
+        self.keep(ffi::DESTROY_NOTIFY, callback);
+    }
+
+    /// Hook running whenever the size of the window changes, e.g. when the user resizes it.
+    ///
+    /// F should be a closure taking 3 arguments: the new width, the new height and the data you pass as last argument.
+    ///
+    /// Usage:
+    /// ```
+    /// window.resize_hook(|width, height, _| {
+    ///     println!("resized to {}x{}", width, height);
+    /// }, &());
+    /// ```
+    ///
+    /// This hooks the `ConfigureNotify` event, which is also received when the window moves: those are skipped.
+    pub fn resize_hook<F, Args>(&self, mut cb: F, args: &'static Args)
+    where
+        F: FnMut(i32, i32, &'static Args) + 'static,
+    {
+        let (_, _, width, height) = ffi::window_geometry(self.display.mlx_ptr, self.ptr());
+        let mut size = Some((width, height));
+        let (event, mask) = (ffi::CONFIGURE_NOTIFY, ffi::STRUCTURE_NOTIFY_MASK);
+        let callback = ffi::hook(
+            self.display.mlx_ptr,
+            self.ptr(),
+            event,
+            mask,
+            move |event| {
+                if let XEventData::Configure { width, height } = event {
+                    if size.replace((width, height)) != Some((width, height)) {
+                        cb(width, height, args);
+                    }
+                }
+            },
+        );
+        self.keep(event, callback);
+    }
+
+    /// Hook running whenever the event you specify occurs.
+    ///
+    /// F should be a closure taking 2 arguments: the [fields of the event](enum.XEventData.html) and the data you pass as last argument of the hook call.
+    ///
+    /// Usage:
+    /// ```
+    /// let arg = (2, 3);
+    /// let x_event = 22; // ConfigureNotify
+    /// let x_mask = 1 << 17; // StructureNotifyMask
+    /// window.hook(x_event, x_mask, |event, args| {
+    ///     if let XEventData::Configure { width, height } = event {
+    ///         println!("{}x{} ({}, {})", width, height, args.0, args.1);
+    ///     }
+    /// }, &arg);
+    /// ```
+    ///
+    /// The fields given for each event type are listed in [XEventData](enum.XEventData.html),
+    /// the other events are given as `XEventData::Other`.
+    ///
+    /// You can find informations on x events in `/usr/include/X11/X.h` around line 180 and x event masks around line 150.
+    pub fn hook<F, Args>(&self, x_event: i32, x_mask: i32, mut cb: F, args: &'static Args)
+    where
+        F: FnMut(XEventData, &'static Args) + 'static,
+    {
+        let callback = ffi::hook(
+            self.display.mlx_ptr,
+            self.ptr(),
+            x_event,
+            x_mask,
+            move |event| {
+                cb(event, args);
+            },
+        );
+        self.keep(x_event, callback);
+    }
+}
+
+/// Modifier keys held down during a key event.
+///
+/// They are read from the X11 state mask, where bit 0 (`ShiftMask`) is shift, bit 2 (`ControlMask`) is control,
+/// and bit 3 (`Mod1Mask`) is alt on most keyboard layouts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    /// a shift key is held down
+    pub shift: bool,
+    /// a control key is held down
+    pub ctrl: bool,
+    /// an alt key is held down
+    pub alt: bool,
+}
+
+impl Modifiers {
+    const SHIFT_MASK: u32 = 1 << 0;
+    const CONTROL_MASK: u32 = 1 << 2;
+    const MOD1_MASK: u32 = 1 << 3;
+
+    /// Reads the modifiers from an X11 state mask.
+    pub fn from_state(state: u32) -> Self {
+        Self {
+            shift: state & Self::SHIFT_MASK != 0,
+            ctrl: state & Self::CONTROL_MASK != 0,
+            alt: state & Self::MOD1_MASK != 0,
+        }
+    }
+}
+
+/// A mouse button, as given to the mouse hooks.
+///
+/// X11 reports the scroll wheel as the buttons 4 and 5, one press per notch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    /// left button, 1
+    Left,
+    /// middle button, 2
+    Middle,
+    /// right button, 3
+    Right,
+    /// scroll wheel up, 4
+    WheelUp,
+    /// scroll wheel down, 5
+    WheelDown,
+    /// any other button, like the side buttons or the horizontal scroll
+    Other(i32),
+}
+
+impl From<i32> for MouseButton {
+    fn from(button: i32) -> Self {
+        match button {
+            1 => Self::Left,
+            2 => Self::Middle,
+            3 => Self::Right,
+            4 => Self::WheelUp,
+            5 => Self::WheelDown,
+            _ => Self::Other(button),
+        }
+    }
+}
+
+/// Enum describing the [endianness](https://en.wikipedia.org/wiki/Endianness) of some data.
+#[derive(Clone, Copy, Debug)]
+pub enum Endian {
+    /// little endian variant.
     Little = 0,
     /// big endian variant.
     Big = 1,
 }
 
 /// Image data placeholder. Can be used to draw image onto the screen.
-#[derive(Clone, Copy)]
+///
+/// The image is freed when it is dropped.
 pub struct MlxImage {
+    display: Rc<Display>,
     img_ptr: *mut c_void,
     /// width of the image
     pub width: i32,
@@ -392,10 +1245,26 @@ pub struct MlxImage {
     pub endian: Endian,
 }
 
+impl Drop for MlxImage {
+    fn drop(&mut self) {
+        ffi::destroy_image(self.display.mlx_ptr, self.img_ptr);
+    }
+}
+
 impl MlxImage {
-    fn new(img_ptr: *mut c_void, width: i32, height: i32) -> Result<Self, MlxError> {
+    fn ptr(&self) -> *mut c_void {
+        self.img_ptr
+    }
+
+    fn new(
+        display: &Rc<Display>,
+        img_ptr: *mut c_void,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, MlxError> {
         let data = ffi::get_data_addr(img_ptr)?;
         Ok(Self {
+            display: display.clone(),
             img_ptr,
             width,
             height,
@@ -417,6 +1286,7 @@ impl MlxImage {
     /// The second bits_per_pixel bits represent the second pixel of the first line, and so on.
     ///
     /// This function could cause a segmentation fault if your offset is wrong, be careful !
+    /// [pixel_put](#method.pixel_put) computes the offset of a pixel and checks it.
     pub fn write_to(&self, offset: i32, value: u8) {
         unsafe {
             *self.area_start.offset(offset as isize) = value as i8;
@@ -428,15 +1298,815 @@ impl MlxImage {
         unsafe { *self.area_start.offset(offset as isize) as u8 }
     }
 
-    /// Writes a pixel to the image.
-    pub fn pixel_put(&self, x: i32, y: i32, color: u32) {
-        let offset = (y * self.size_line + x * self.bits_per_pixel / 8) as i32;
-        let color = color.to_ne_bytes();
-        if offset < 0 || offset >= self.size_line * self.height {
+    /// Writes a pixel to the image, nothing is written when `(x, y)` is outside of the image.
+    ///
+    /// The offset of the pixel is computed from [size_line](struct.MlxImage.html#structfield.size_line)
+    /// and [bits_per_pixel](struct.MlxImage.html#structfield.bits_per_pixel),
+    /// and the bytes of the color are written in the [endian](struct.MlxImage.html#structfield.endian)
+    /// order of the image, so `color` is always `0xRRGGBB`.
+    ///
+    /// Usage:
+    ///```
+    /// image.pixel_put(10, 20, colors::RED);
+    ///```
+    pub fn pixel_put(&self, x: i32, y: i32, color: impl Into<Color>) {
+        let Some(offset) = self.pixel_offset(x, y) else {
             return;
+        };
+        let bytes = self.color_bytes(color.into());
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                self.area_start.add(offset) as *mut u8,
+                self.bytes_per_pixel(),
+            );
+        }
+    }
+
+    /// Sets every pixel of the image to `color`.
+    ///
+    /// The content of a new image is undefined, this gives it a known background.
+    /// Only the `width` pixels of each line are written, the padding up to [size_line](struct.MlxImage.html#structfield.size_line) is left alone.
+    ///
+    /// Usage:
+    ///```
+    /// let image = mlx.new_image(200, 100)?;
+    /// image.clear(colors::BLACK);
+    ///```
+    pub fn clear(&self, color: impl Into<Color>) {
+        let bytes = self.color_bytes(color.into());
+        for y in 0..self.height {
+            self.fill_row(y, 0, self.width, &bytes);
+        }
+    }
+
+    /// Reads the `0xRRGGBB` color of a pixel of the image, `None` when `(x, y)` is outside of the image.
+    ///
+    /// The bytes of the pixel are read in the [endian](struct.MlxImage.html#structfield.endian) order of the image,
+    /// the alpha byte of the 32 bits per pixel images is left out.
+    ///
+    /// Usage:
+    ///```
+    /// image.pixel_put(10, 20, colors::RED);
+    /// assert_eq!(image.get_pixel(10, 20), Some(colors::RED));
+    ///```
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<u32> {
+        let offset = self.pixel_offset(x, y)?;
+        let format = self.format();
+        let mut bytes = [0; 4];
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                self.area_start.add(offset) as *const u8,
+                bytes.as_mut_ptr(),
+                format.bytes_per_pixel,
+            );
+        }
+        Some(format.decode(&bytes))
+    }
+
+    /// Writes the image to a binary PPM (P6) file, 8 bits per channel.
+    ///
+    /// The pixels are read with [get_pixel](#method.get_pixel), so any depth and byte order is supported.
+    ///
+    /// Usage:
+    ///```
+    /// image.save_ppm("screenshot.ppm")?;
+    ///```
+    pub fn save_ppm(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = self.get_pixel(x, y).unwrap_or_default();
+                file.write_all(&[(color >> 16) as u8, (color >> 8) as u8, color as u8])?;
+            }
+        }
+        file.flush()
+    }
+
+    /// Writes the image to an uncompressed 24 bits [BMP](https://en.wikipedia.org/wiki/BMP_file_format) file.
+    ///
+    /// The rows are stored from the bottom one up, each padded to a multiple of 4 bytes,
+    /// with the pixels as blue, green and red bytes and the header fields in little endian.
+    ///
+    /// Usage:
+    ///```
+    /// image.save_bmp("screenshot.bmp")?;
+    ///```
+    pub fn save_bmp(&self, path: &str) -> io::Result<()> {
+        let mut pixels = Vec::with_capacity((self.width.max(0) * self.height.max(0)) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                pixels.push(self.get_pixel(x, y).unwrap_or_default());
+            }
         }
-        for i in 0..(self.bits_per_pixel / 8) {
-            self.write_to(offset + i, color[i as usize]);
+        let mut file = BufWriter::new(File::create(path)?);
+        write_bmp(
+            &mut file,
+            self.width.max(0) as u32,
+            self.height.max(0) as u32,
+            &pixels,
+        )?;
+        file.flush()
+    }
+
+    /// Bytes of `color` as stored in a pixel, in the endian order of the image, see [PixelFormat::encode].
+    fn color_bytes(&self, color: Color) -> [u8; 4] {
+        self.format().encode(color.as_u32())
+    }
+
+    fn format(&self) -> PixelFormat {
+        PixelFormat {
+            bytes_per_pixel: self.bytes_per_pixel(),
+            endian: self.endian,
         }
     }
+
+    /// Writes the pixels from `left` to `right` (excluded) of the row `y` with the [bytes of a color](#method.color_bytes).
+    ///
+    /// The span is clipped to the image.
+    fn fill_row(&self, y: i32, left: i32, right: i32, bytes: &[u8; 4]) {
+        let (left, right) = (left.max(0), right.min(self.width));
+        let Some(start) = self.pixel_offset(left, y).filter(|_| left < right) else {
+            return;
+        };
+        let bytes_per_pixel = self.bytes_per_pixel();
+        unsafe {
+            let mut pixel = self.area_start.add(start) as *mut u8;
+            for _ in left..right {
+                std::ptr::copy_nonoverlapping(bytes.as_ptr(), pixel, bytes_per_pixel);
+                pixel = pixel.add(bytes_per_pixel);
+            }
+        }
+    }
+
+    /// Number of bytes of a pixel, 4 at most.
+    fn bytes_per_pixel(&self) -> usize {
+        (self.bits_per_pixel / 8).clamp(0, 4) as usize
+    }
+
+    /// Offset of the pixel `(x, y)` from the start of the image, `None` when it is outside of the image.
+    fn pixel_offset(&self, x: i32, y: i32) -> Option<usize> {
+        let size = (self.width, self.height);
+        pixel_offset(x, y, size, self.size_line, self.bytes_per_pixel())
+    }
+
+    /// Iterates over the rows of the image, from top to bottom, each one being `width` pixels long.
+    ///
+    /// The rows are not contiguous in memory: a line can take more than `width * 4` bytes
+    /// (see [size_line](struct.MlxImage.html#structfield.size_line)), the padding at the end of each line is skipped.
+    ///
+    /// Only 32 bits per pixel images are supported, this function panics for other depths.
+    /// The image is borrowed exclusively while the rows are alive, as the drawing methods write to it
+    /// through a shared borrow.
+    ///
+    /// Usage:
+    ///```
+    /// for (y, row) in image.rows_mut().enumerate() {
+    ///     for (x, pixel) in row.iter_mut().enumerate() {
+    ///         *pixel = if (x + y) % 2 == 0 { 0xffffff } else { 0 };
+    ///     }
+    /// }
+    ///```
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u32]> {
+        assert_eq!(self.bits_per_pixel, 32, "rows_mut needs 32 bits per pixel");
+        let area_start = self.area_start;
+        let (width, size_line) = (self.width as usize, self.size_line as usize);
+
+        (0..self.height as usize).map(move |y| unsafe {
+            // lines are aligned on 4 bytes when there are 32 bits per pixel
+            std::slice::from_raw_parts_mut(area_start.add(y * size_line) as *mut u32, width)
+        })
+    }
+
+    /// Bytes of the image, [size_line](struct.MlxImage.html#structfield.size_line) times `height` of them,
+    /// in the depth and [endian](struct.MlxImage.html#structfield.endian) order of the image.
+    ///
+    /// The drawing methods write to the image through a shared borrow, so the image is borrowed exclusively
+    /// for as long as the slice is alive, which keeps it from changing under the slice.
+    ///
+    /// Usage:
+    ///```
+    /// let bytes = image.as_slice();
+    /// let first_line = &bytes[..image.size_line as usize];
+    ///```
+    pub fn as_slice(&mut self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.area_start as *const u8, self.data_len()) }
+    }
+
+    /// Bytes of the image to write to, laid out as in [as_slice](#method.as_slice),
+    /// including the padding at the end of each line.
+    ///
+    /// Usage:
+    ///```
+    /// let size_line = image.size_line as usize;
+    /// for (y, line) in image.as_mut_slice().chunks_mut(size_line).enumerate() {
+    ///     line.fill(if y % 2 == 0 { 0xff } else { 0 });
+    /// }
+    ///```
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.area_start as *mut u8, self.data_len()) }
+    }
+
+    /// Iterates over the pixels of the image with their position, row after row, skipping the padding.
+    ///
+    /// Only 32 bits per pixel images are supported, this function panics for other depths.
+    ///
+    /// Usage:
+    ///```
+    /// for (x, y, pixel) in image.pixels_mut() {
+    ///     *pixel = (x ^ y) as u32 & 0xff;
+    /// }
+    ///```
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = (i32, i32, &mut u32)> {
+        self.rows_mut()
+            .zip(0..)
+            .flat_map(|(row, y)| row.iter_mut().zip(0..).map(move |(pixel, x)| (x, y, pixel)))
+    }
+
+    /// Number of bytes of the image, padding included.
+    fn data_len(&self) -> usize {
+        self.size_line.max(0) as usize * self.height.max(0) as usize
+    }
+
+    /// Sets every pixel of the image to the color `f(x, y)`, computing the rows on all the cores.
+    ///
+    /// The rows are handed out one at a time to a thread per core, which write them as disjoint slices
+    /// from [rows_mut](#method.rows_mut): `f` only needs to be shareable between the threads (`Sync`).
+    /// The threads are joined before this returns.
+    ///
+    /// Only 32 bits per pixel images are supported, this function panics for other depths.
+    ///
+    /// Usage:
+    ///```
+    /// image.render_parallel(|x, y| if (x + y) % 2 == 0 { 0xffffff } else { 0 });
+    ///```
+    pub fn render_parallel<F>(&mut self, f: F)
+    where
+        F: Fn(i32, i32) -> u32 + Sync,
+    {
+        let rows: Vec<&mut [u32]> = self.rows_mut().collect();
+        let rows = Mutex::new(rows.into_iter().enumerate());
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let Some((y, row)) = rows.lock().unwrap().next() else {
+                        break;
+                    };
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        *pixel = f(x as i32, y as i32);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Fills a rectangle of the image, the parts outside of the image are skipped.
+    ///
+    /// Usage:
+    ///```
+    /// image.fill_rect(10, 10, 100, 50, colors::RED);
+    ///```
+    pub fn fill_rect(&self, x: i32, y: i32, width: i32, height: i32, color: impl Into<Color>) {
+        let bytes = self.color_bytes(color.into());
+        for (row, columns) in clip_rect(x, y, width, height, (self.width, self.height)) {
+            self.fill_row(row, columns.start, columns.end, &bytes);
+        }
+    }
+
+    /// Fills a disc of radius `radius` centered on `(cx, cy)`, the pixels outside of the image are skipped.
+    ///
+    /// A pixel is filled when its distance to the center is at most the radius, so a radius of 0 fills the center only.
+    ///
+    /// Usage:
+    ///```
+    /// image.fill_circle(100, 100, 20, colors::YELLOW);
+    ///```
+    pub fn fill_circle(&self, cx: i32, cy: i32, radius: i32, color: impl Into<Color>) {
+        let bytes = self.color_bytes(color.into());
+        for dy in -radius..=radius {
+            let half = ((radius * radius - dy * dy) as f64).sqrt() as i32;
+            self.fill_row(cy + dy, cx - half, cx + half + 1, &bytes);
+        }
+    }
+
+    /// Copies the `width` x `height` region of `src` with its top left corner at `(src_x, src_y)`
+    /// to `(dst_x, dst_y)` in this image.
+    ///
+    /// The region is clipped to both images. The rows are copied as is when both images have
+    /// the same [bits_per_pixel](#structfield.bits_per_pixel) and [endian](#structfield.endian),
+    /// and the pixels are converted one by one otherwise. `src` can be this image, even when the regions overlap.
+    ///
+    /// Usage:
+    ///```
+    /// // composites a static HUD layer over the fractal before drawing the frame
+    /// frame.blit_from(&hud, 0, 0, hud.width, hud.height, 10, 10);
+    /// mlx.put_image_to_window(&window, &frame, 0, 0);
+    ///```
+    #[allow(clippy::too_many_arguments)]
+    pub fn blit_from(
+        &self,
+        src: &MlxImage,
+        src_x: i32,
+        src_y: i32,
+        width: i32,
+        height: i32,
+        dst_x: i32,
+        dst_y: i32,
+    ) {
+        let (mut src_x, mut src_y, mut dst_x, mut dst_y) = (src_x, src_y, dst_x, dst_y);
+        let (mut width, mut height) = (width, height);
+        // moves the corners inside of the images, shrinking the region by as much
+        let shift = (-src_x).max(-dst_x).max(0);
+        (src_x, dst_x, width) = (src_x + shift, dst_x + shift, width - shift);
+        let shift = (-src_y).max(-dst_y).max(0);
+        (src_y, dst_y, height) = (src_y + shift, dst_y + shift, height - shift);
+        let width = width.min(src.width - src_x).min(self.width - dst_x);
+        let height = height.min(src.height - src_y).min(self.height - dst_y);
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        // rows are copied bottom to top when they move down in the same image, not to overwrite the next ones
+        let rows: Box<dyn Iterator<Item = i32>> = if dst_y > src_y {
+            Box::new((0..height).rev())
+        } else {
+            Box::new(0..height)
+        };
+        let same_format =
+            self.bits_per_pixel == src.bits_per_pixel && self.endian as i32 == src.endian as i32;
+        if !same_format {
+            for y in rows {
+                for x in 0..width {
+                    if let Some(color) = src.get_pixel(src_x + x, src_y + y) {
+                        self.pixel_put(dst_x + x, dst_y + y, color);
+                    }
+                }
+            }
+            return;
+        }
+
+        let length = width as usize * self.bytes_per_pixel();
+        for y in rows {
+            let (Some(from), Some(to)) = (
+                src.pixel_offset(src_x, src_y + y),
+                self.pixel_offset(dst_x, dst_y + y),
+            ) else {
+                continue;
+            };
+            unsafe {
+                std::ptr::copy(src.area_start.add(from), self.area_start.add(to), length);
+            }
+        }
+    }
+
+    /// Draws the one pixel wide outline of a rectangle, from its top left corner and its size.
+    ///
+    /// Like [fill_rect](#method.fill_rect), the parts of the outline outside of the image are skipped.
+    ///
+    /// Usage:
+    ///```
+    /// image.draw_rect(10, 10, 100, 50, colors::WHITE);
+    ///```
+    pub fn draw_rect(&self, x: i32, y: i32, width: i32, height: i32, color: impl Into<Color>) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let color = color.into();
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.fill_rect(x, y, width, 1, color);
+        self.fill_rect(x, bottom, width, 1, color);
+        self.fill_rect(x, y, 1, height, color);
+        self.fill_rect(right, y, 1, height, color);
+    }
+
+    /// Writes a line of text into the image `scale` times larger than its base size, with `y` as the baseline.
+    ///
+    /// Uses the same font as [Mlx::string_put_scaled](struct.Mlx.html#method.string_put_scaled),
+    /// the pixels falling outside of the image are skipped.
+    ///
+    /// Usage:
+    ///```
+    /// image.string_put_scaled(10, 40, colors::WHITE, 2, "42 iterations");
+    ///```
+    pub fn string_put_scaled(&self, x: i32, y: i32, color: impl Into<Color>, scale: i32, s: &str) {
+        let color = color.into();
+        let scale = scale.max(1);
+        let top = y - font::GLYPH_HEIGHT * scale;
+        for (px, py) in font::pixels(s) {
+            self.fill_rect(x + px * scale, top + py * scale, scale, scale, color);
+        }
+    }
+
+    /// Draws a line between two points of the image using [Bresenham's algorithm](https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm).
+    ///
+    /// Points of the line falling outside of the image are skipped.
+    ///
+    /// Usage:
+    ///```
+    /// image.draw_line(0, 0, image.width - 1, image.height - 1, colors::WHITE);
+    ///```
+    pub fn draw_line(&self, x0: i32, y0: i32, x1: i32, y1: i32, color: impl Into<Color>) {
+        let color = color.into();
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            if x >= 0 && x < self.width && y >= 0 && y < self.height {
+                self.pixel_put(x, y, color);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+}
+
+/// How the color of a pixel is stored in the memory of an image.
+#[derive(Clone, Copy, Debug)]
+struct PixelFormat {
+    /// 4 at most
+    bytes_per_pixel: usize,
+    endian: Endian,
+}
+
+impl PixelFormat {
+    /// Bytes of the `0xRRGGBB` color as stored in a pixel.
+    ///
+    /// Only the first `bytes_per_pixel` bytes are used:
+    /// the low bytes of the color, which hold it when there are less than 32 bits per pixel.
+    fn encode(self, color: u32) -> [u8; 4] {
+        let (little, big) = (color.to_le_bytes(), color.to_be_bytes());
+        let n = self.bytes_per_pixel;
+        let mut bytes = [0; 4];
+        bytes[..n].copy_from_slice(match self.endian {
+            Endian::Little => &little[..n],
+            Endian::Big => &big[4 - n..],
+        });
+        bytes
+    }
+
+    /// `0xRRGGBB` color of a pixel from its stored bytes, the first `bytes_per_pixel` ones of `bytes`.
+    ///
+    /// The alpha byte of the 32 bits per pixel formats is left out.
+    fn decode(self, bytes: &[u8; 4]) -> u32 {
+        let n = self.bytes_per_pixel;
+        let mut padded = [0; 4];
+        let color = match self.endian {
+            Endian::Little => {
+                padded[..n].copy_from_slice(&bytes[..n]);
+                u32::from_le_bytes(padded)
+            }
+            Endian::Big => {
+                padded[4 - n..].copy_from_slice(&bytes[..n]);
+                u32::from_be_bytes(padded)
+            }
+        };
+        color & 0xffffff
+    }
+}
+
+/// Offset of the pixel `(x, y)` from the start of an image of `size`, `None` when it is outside of the image.
+fn pixel_offset(
+    x: i32,
+    y: i32,
+    size: (i32, i32),
+    size_line: i32,
+    bytes_per_pixel: usize,
+) -> Option<usize> {
+    if x < 0 || y < 0 || x >= size.0 || y >= size.1 {
+        return None;
+    }
+    Some(y as usize * size_line as usize + x as usize * bytes_per_pixel)
+}
+
+/// Pixels of the `width` x `height` rectangle at `(x, y)` within an image of `size`, as each row
+/// with its span of columns, the parts outside of the image being cut off.
+fn clip_rect(
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    size: (i32, i32),
+) -> impl Iterator<Item = (i32, Range<i32>)> {
+    let columns = x.max(0)..x.saturating_add(width).min(size.0);
+    let rows = y.max(0)..y.saturating_add(height).min(size.1);
+    // an empty span of columns gives no rows
+    let rows = if columns.is_empty() { 0..0 } else { rows };
+    rows.map(move |row| (row, columns.clone()))
+}
+
+/// Writes `width * height` pixels of `0xRRGGBB`, row after row from the top, as an uncompressed 24 bits BMP file,
+/// see [save_bmp](struct.MlxImage.html#method.save_bmp).
+fn write_bmp(out: &mut impl Write, width: u32, height: u32, pixels: &[u32]) -> io::Result<()> {
+    const HEADERS_SIZE: u32 = 14 + 40;
+    let row_size = (width * 3).next_multiple_of(4);
+    let padding = [0; 3];
+    let padding = &padding[..(row_size - width * 3) as usize];
+
+    // file header: signature, file size, two reserved fields, offset of the pixels
+    out.write_all(b"BM")?;
+    out.write_all(&(HEADERS_SIZE + row_size * height).to_le_bytes())?;
+    out.write_all(&[0; 4])?;
+    out.write_all(&HEADERS_SIZE.to_le_bytes())?;
+    // info header: its size, the size of the image, 1 plane, 24 bits per pixel, no compression,
+    // size of the pixels, 2835 pixels per meter (72 dpi) and no color table
+    out.write_all(&40u32.to_le_bytes())?;
+    out.write_all(&(width as i32).to_le_bytes())?;
+    out.write_all(&(height as i32).to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&24u16.to_le_bytes())?;
+    out.write_all(&0u32.to_le_bytes())?;
+    out.write_all(&(row_size * height).to_le_bytes())?;
+    out.write_all(&2835i32.to_le_bytes())?;
+    out.write_all(&2835i32.to_le_bytes())?;
+    out.write_all(&[0; 8])?;
+
+    if width == 0 {
+        return Ok(());
+    }
+    for row in pixels.chunks(width as usize).take(height as usize).rev() {
+        for &color in row {
+            out.write_all(&[color as u8, (color >> 8) as u8, (color >> 16) as u8])?;
+        }
+        out.write_all(padding)?;
+    }
+    Ok(())
+}
+
+/// Two images of the same size: one is shown in the window while the other one is drawn.
+///
+/// The minilibx windows are single-buffered, the window shows whatever was last put into it.
+/// Drawing a frame in several steps straight into the window, or into an image the X server is still reading
+/// (which happens with the MIT-SHM extension, see [present_synced](struct.Mlx.html#method.present_synced)),
+/// shows partial frames. This renders into the [back](#method.back) image instead,
+/// and [present](#method.present) puts it into the window once it is complete.
+///
+/// Usage:
+///```
+/// let mut buffer = DoubleBuffer::new(&mlx, 1080, 720)?;
+/// buffer.back().clear(colors::BLACK);
+/// buffer.back().fill_circle(540, 360, 100, colors::RED);
+/// buffer.present(&window);
+///```
+pub struct DoubleBuffer {
+    mlx: Mlx,
+    images: [MlxImage; 2],
+    back: usize,
+}
+
+impl DoubleBuffer {
+    /// Creates the two images, of `width` x `height` pixels.
+    pub fn new(mlx: &Mlx, width: i32, height: i32) -> Result<Self, MlxError> {
+        Ok(Self {
+            mlx: mlx.clone(),
+            images: [mlx.new_image(width, height)?, mlx.new_image(width, height)?],
+            back: 0,
+        })
+    }
+
+    /// The image being drawn, which is not shown.
+    pub fn back(&self) -> &MlxImage {
+        &self.images[self.back]
+    }
+
+    /// The image last presented.
+    pub fn front(&self) -> &MlxImage {
+        &self.images[1 - self.back]
+    }
+
+    /// Swaps the images, the back one becoming the front one, without drawing anything.
+    ///
+    /// The new back image still holds the frame before the last one.
+    pub fn swap(&mut self) {
+        self.back = 1 - self.back;
+    }
+
+    /// Swaps the images and puts the new front one, the frame just drawn, into the top left corner of the window.
+    pub fn present(&mut self, window: &MlxWindow) {
+        self.swap();
+        self.mlx.put_image_to_window(window, self.front(), 0, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Writes `color` at `(x, y)` in a buffer laid out like an image, as [MlxImage::pixel_put] does.
+    fn put(buffer: &mut [u8], layout: ((i32, i32), i32, PixelFormat), x: i32, y: i32, color: u32) {
+        let (size, size_line, format) = layout;
+        if let Some(offset) = pixel_offset(x, y, size, size_line, format.bytes_per_pixel) {
+            let n = format.bytes_per_pixel;
+            buffer[offset..offset + n].copy_from_slice(&format.encode(color)[..n]);
+        }
+    }
+
+    /// Reads the color at `(x, y)` in a buffer laid out like an image, as [MlxImage::get_pixel] does.
+    fn get(buffer: &[u8], layout: ((i32, i32), i32, PixelFormat), x: i32, y: i32) -> Option<u32> {
+        let (size, size_line, format) = layout;
+        let offset = pixel_offset(x, y, size, size_line, format.bytes_per_pixel)?;
+        let mut bytes = [0; 4];
+        bytes[..format.bytes_per_pixel]
+            .copy_from_slice(&buffer[offset..offset + format.bytes_per_pixel]);
+        Some(format.decode(&bytes))
+    }
+
+    #[test]
+    fn pixels_round_trip_through_a_32_bits_little_endian_buffer() {
+        let format = PixelFormat {
+            bytes_per_pixel: 4,
+            endian: Endian::Little,
+        };
+        // 3 pixels per line, padded to 16 bytes
+        let layout = ((3, 2), 16, format);
+        let mut buffer = vec![0xaa; 16 * 2];
+        for y in 0..2 {
+            for x in 0..3 {
+                let color = Color::rgb(x as u8 * 80, y as u8 * 120, 0x33).as_u32();
+                put(&mut buffer, layout, x, y, color);
+                assert_eq!(get(&buffer, layout, x, y), Some(color));
+            }
+        }
+        // blue, green, red then the unused byte
+        assert_eq!(&buffer[20..24], &[0x33, 0x78, 0x50, 0]);
+        assert_eq!(&buffer[12..16], &[0xaa; 4]);
+        assert_eq!(get(&buffer, layout, 3, 0), None);
+        assert_eq!(get(&buffer, layout, 0, -1), None);
+    }
+
+    #[test]
+    fn pixels_round_trip_in_every_format() {
+        for (bytes_per_pixel, endian) in [
+            (4, Endian::Big),
+            (3, Endian::Little),
+            (3, Endian::Big),
+            (2, Endian::Little),
+            (2, Endian::Big),
+        ] {
+            let format = PixelFormat {
+                bytes_per_pixel,
+                endian,
+            };
+            // the lines are padded past the 4 pixels
+            let size_line = 4 * bytes_per_pixel as i32 + 3;
+            let layout = ((4, 3), size_line, format);
+            let mut buffer = vec![0; size_line as usize * 3];
+            // only the low bytes fit in the 16 bits formats
+            let mask = ((1u64 << (8 * bytes_per_pixel)) - 1) as u32 & 0xffffff;
+            let color = |x: i32, y: i32| (0x10 * x as u32 + 0x0f0301 * y as u32 + 0x405060) & mask;
+            for y in 0..3 {
+                for x in 0..4 {
+                    put(&mut buffer, layout, x, y, color(x, y));
+                }
+            }
+            for y in 0..3 {
+                for x in 0..4 {
+                    let read = get(&buffer, layout, x, y);
+                    assert_eq!(
+                        read,
+                        Some(color(x, y)),
+                        "{} bytes {:?}",
+                        bytes_per_pixel,
+                        endian
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn big_endian_pixels_store_the_high_byte_first() {
+        let format = PixelFormat {
+            bytes_per_pixel: 4,
+            endian: Endian::Big,
+        };
+        assert_eq!(format.encode(0x123456), [0x00, 0x12, 0x34, 0x56]);
+        let format = PixelFormat {
+            bytes_per_pixel: 3,
+            endian: Endian::Big,
+        };
+        assert_eq!(&format.encode(0x123456)[..3], &[0x12, 0x34, 0x56]);
+        assert_eq!(format.decode(&[0x12, 0x34, 0x56, 0xff]), 0x123456);
+    }
+
+    #[test]
+    fn pixel_offsets_skip_the_line_padding() {
+        assert_eq!(pixel_offset(0, 0, (10, 5), 48, 4), Some(0));
+        assert_eq!(pixel_offset(9, 0, (10, 5), 48, 4), Some(36));
+        assert_eq!(pixel_offset(2, 3, (10, 5), 48, 4), Some(3 * 48 + 8));
+        assert_eq!(pixel_offset(10, 0, (10, 5), 48, 4), None);
+        assert_eq!(pixel_offset(0, 5, (10, 5), 48, 4), None);
+        assert_eq!(pixel_offset(-1, 2, (10, 5), 48, 4), None);
+    }
+
+    #[test]
+    fn hsv_colors_match_the_basic_colors() {
+        assert_eq!(Color::hsv(0.0, 1.0, 1.0).as_u32(), colors::RED);
+        assert_eq!(Color::hsv(120.0, 1.0, 1.0).as_u32(), colors::LIME);
+        assert_eq!(Color::hsv(240.0, 1.0, 1.0).as_u32(), colors::BLUE);
+        assert_eq!(Color::hsv(-60.0, 1.0, 1.0).as_u32(), colors::FUCHSIA);
+        assert_eq!(
+            Color::hsv(60.0, 0.0, 0.5).as_u32(),
+            Color::rgb(128, 128, 128).as_u32()
+        );
+    }
+
+    /// Fills the rectangle in a 6x4 buffer of zeros, as [MlxImage::fill_rect] does in an image.
+    fn fill(x: i32, y: i32, width: i32, height: i32) -> [[u8; 6]; 4] {
+        let mut buffer = [[0; 6]; 4];
+        for (row, columns) in clip_rect(x, y, width, height, (6, 4)) {
+            for column in columns {
+                buffer[row as usize][column as usize] = 1;
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn rect_is_clipped_to_the_image() {
+        assert_eq!(fill(0, 0, 6, 4), [[1; 6]; 4]);
+        assert_eq!(
+            fill(1, 1, 2, 2),
+            [
+                [0, 0, 0, 0, 0, 0],
+                [0, 1, 1, 0, 0, 0],
+                [0, 1, 1, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0],
+            ]
+        );
+        // partly off the top left corner
+        assert_eq!(
+            fill(-2, -1, 4, 3),
+            [
+                [1, 1, 0, 0, 0, 0],
+                [1, 1, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0],
+            ]
+        );
+        // partly off the bottom right corner
+        assert_eq!(
+            fill(4, 2, 10, 10),
+            [
+                [0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 1, 1],
+                [0, 0, 0, 0, 1, 1],
+            ]
+        );
+        // entirely outside, empty, or too large to add up
+        assert_eq!(fill(-5, 0, 3, 2), [[0; 6]; 4]);
+        assert_eq!(fill(6, 0, 3, 2), [[0; 6]; 4]);
+        assert_eq!(fill(2, 2, 0, 5), [[0; 6]; 4]);
+        assert_eq!(fill(0, 3, i32::MAX, i32::MAX)[3], [1; 6]);
+    }
+
+    #[test]
+    fn bmp_rows_are_bottom_up_and_padded() {
+        // 3 pixels wide: 9 bytes of pixels padded to 12 on each row
+        let pixels = [0x010203, 0x040506, 0x070809, 0x0a0b0c, 0x0d0e0f, 0x101112];
+        let mut bmp = Vec::new();
+        write_bmp(&mut bmp, 3, 2, &pixels).unwrap();
+
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32_at(&bmp, 2), 54 + 2 * 12);
+        assert_eq!(bmp.len(), 54 + 2 * 12);
+        assert_eq!(u32_at(&bmp, 10), 54);
+        assert_eq!(u32_at(&bmp, 14), 40);
+        assert_eq!(u32_at(&bmp, 18), 3);
+        assert_eq!(u32_at(&bmp, 22), 2);
+        assert_eq!(&bmp[26..30], &[1, 0, 24, 0]);
+        assert_eq!(u32_at(&bmp, 30), 0);
+        assert_eq!(u32_at(&bmp, 34), 2 * 12);
+
+        // the bottom row first, in blue, green, red order
+        let bottom = [
+            0x0c, 0x0b, 0x0a, 0x0f, 0x0e, 0x0d, 0x12, 0x11, 0x10, 0, 0, 0,
+        ];
+        let top = [
+            0x03, 0x02, 0x01, 0x06, 0x05, 0x04, 0x09, 0x08, 0x07, 0, 0, 0,
+        ];
+        assert_eq!(&bmp[54..66], &bottom);
+        assert_eq!(&bmp[66..78], &top);
+    }
 }
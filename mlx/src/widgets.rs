@@ -0,0 +1,126 @@
+//! Immediate-mode widgets, enabled with the `widgets` feature.
+//!
+//! Widgets keep no state: they are drawn and checked for clicks every time the frame is drawn.
+//! Shapes are drawn into the image and labels into the window, so the labels are queued
+//! until the image has been put to the window.
+//!
+//! Usage:
+//!```
+//! let mut ui = Ui::new(&image, mouse);
+//! if ui.button(Rect::new(10, 10, 80, 20), "reset") {
+//!     println!("reset clicked");
+//! }
+//! mlx.put_image_to_window(&window, &image, 0, 0);
+//! ui.draw_labels(&mlx, &window);
+//!```
+
+use crate::{colors, Mlx, MlxImage, MlxWindow};
+
+const BUTTON_COLOR: u32 = 0x303030;
+const HOVERED_COLOR: u32 = 0x505050;
+const PRESSED_COLOR: u32 = 0x707070;
+const BORDER_COLOR: u32 = 0xa0a0a0;
+const LABEL_COLOR: u32 = colors::WHITE;
+
+/// Width of a character of the default font, in pixels.
+pub const CHAR_WIDTH: i32 = 6;
+/// Height of a character of the default font, in pixels.
+pub const CHAR_HEIGHT: i32 = 10;
+
+/// Rectangle of pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    /// left side
+    pub x: i32,
+    /// top side
+    pub y: i32,
+    /// width in pixels
+    pub width: i32,
+    /// height in pixels
+    pub height: i32,
+}
+
+impl Rect {
+    /// Creates a rectangle from its top left corner and its size.
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Whether the pixel `(x, y)` is inside of the rectangle.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// State of the mouse, tracked by the application from its hooks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MouseState {
+    /// position of the pointer in the window
+    pub x: i32,
+    /// position of the pointer in the window
+    pub y: i32,
+    /// the left button is held down
+    pub down: bool,
+    /// the left button was released since the last frame
+    pub released: bool,
+}
+
+/// Context in which the widgets of a frame are drawn.
+pub struct Ui<'a> {
+    image: &'a MlxImage,
+    mouse: MouseState,
+    labels: Vec<(i32, i32, String)>,
+}
+
+impl<'a> Ui<'a> {
+    /// Starts a frame drawn into `image`.
+    pub fn new(image: &'a MlxImage, mouse: MouseState) -> Self {
+        Self {
+            image,
+            mouse,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Draws a button and returns whether it was clicked this frame.
+    ///
+    /// Usage:
+    ///```
+    /// if ui.button(Rect::new(10, 10, 80, 20), "reset") {
+    ///     println!("reset clicked");
+    /// }
+    ///```
+    pub fn button(&mut self, rect: Rect, label: &str) -> bool {
+        let hovered = rect.contains(self.mouse.x, self.mouse.y);
+        let color = match (hovered, self.mouse.down) {
+            (true, true) => PRESSED_COLOR,
+            (true, false) => HOVERED_COLOR,
+            _ => BUTTON_COLOR,
+        };
+
+        self.image
+            .fill_rect(rect.x, rect.y, rect.width, rect.height, color);
+        self.image
+            .draw_rect(rect.x, rect.y, rect.width, rect.height, BORDER_COLOR);
+
+        // strings are drawn from their baseline
+        let text_width = CHAR_WIDTH * label.chars().count() as i32;
+        let x = rect.x + (rect.width - text_width) / 2;
+        let y = rect.y + (rect.height + CHAR_HEIGHT) / 2;
+        self.labels.push((x, y, label.to_string()));
+
+        hovered && self.mouse.released
+    }
+
+    /// Draws the labels of the widgets, once the image has been put to the window.
+    pub fn draw_labels(self, mlx: &Mlx, window: &MlxWindow) {
+        for (x, y, label) in &self.labels {
+            mlx.string_put_truncated(window, *x, *y, LABEL_COLOR, label);
+        }
+    }
+}
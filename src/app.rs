@@ -0,0 +1,957 @@
+use crate::export::{self, ExportConfig};
+use crate::fractal::{Mode, FRACTALS};
+use crate::keymap::{Action, Keymap};
+use crate::overlay;
+use crate::palette::Palette;
+use crate::render::{RenderConfig, Renderer, Tile, TiledRender, MAX_DEPTH, MAX_POINTS, MIN_POINTS};
+use crate::rng::Rng;
+use crate::session::{Session, SESSION_FILE};
+use crate::settings::Settings;
+use crate::sweep::Sweep;
+use crate::timer::FrameTimer;
+use crate::tour::{Playback, Recorder, Tour, TOUR_FILE};
+use crate::viewport::Viewport;
+use mlx::widgets::{MouseState, Ui};
+use mlx::{colors, Mlx, MlxError, MlxImage, MlxWindow, Modifiers, MouseButton, LINE_HEIGHT};
+use num_complex::Complex;
+use std::mem;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// the view shrinks or grows by this factor for each step of the wheel
+const WHEEL_ZOOM: f64 = 1.2;
+/// smaller selections are treated as a click
+const MIN_SELECTION: i32 = 3;
+
+/// Fraction of the width or height of the view moved by a pan key.
+pub const PAN_STEP: f64 = 0.1;
+
+/// The Julia constant picked with the mouse stays within this distance of 0 on each axis.
+const PICK_LIMIT: f64 = 2.0;
+
+/// Time spent rendering tiles in each loop iteration, so the input is handled in between.
+const FRAME_BUDGET: Duration = Duration::from_millis(25);
+
+/// The preview region is this many times smaller than the window on each side.
+const PREVIEW_FRACTION: i32 = 3;
+
+const MIN_ITERATIONS: u32 = 10;
+const MAX_ITERATIONS: u32 = 2000;
+/// iterations added or removed by a key press
+const ITERATIONS_STEP: u32 = 10;
+/// fraction of the palette rotated by a key press
+const PALETTE_OFFSET_STEP: f64 = 0.05;
+/// factor of the palette contrast for a key press
+const PALETTE_CONTRAST_STEP: f64 = 1.25;
+const MIN_PALETTE_CONTRAST: f64 = 1.0 / 16.0;
+const MAX_PALETTE_CONTRAST: f64 = 16.0;
+/// size of the palette swatches written by [Action::ExportPalette]
+const PALETTE_SWATCH_SIZE: (i32, i32) = (256, 32);
+/// largest divisor of the resolution, the divisors are powers of two
+const MAX_RESOLUTION_DIVISOR: i32 = 8;
+/// largest supersampling factor of the antialiasing key, the factors are powers of two
+const MAX_SUPERSAMPLING: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+/// Other side of the split screen, both sides showing their half of the same view.
+///
+/// The mode and settings of the side being edited are the ones of [App],
+/// they are swapped with these when the mouse goes over the other side.
+struct Split {
+    mode: Mode,
+    config: RenderConfig,
+    job: Option<TiledRender>,
+    frame: Vec<u32>,
+    /// side of the mode and settings of [App]
+    edited: Side,
+}
+
+/// State shared by all the hooks.
+pub struct App {
+    mlx: Mlx,
+    window: MlxWindow,
+    image: MlxImage,
+    mode: Mode,
+    viewport: Viewport,
+    config: RenderConfig,
+    export: ExportConfig,
+    keymap: Keymap,
+    /// side of the render tiles, picked for each frame when `None`
+    tile_size: Option<i32>,
+    /// render in progress
+    job: Option<TiledRender>,
+    /// last rendered fractal, overlays are drawn on top of it so it does not need to be recomputed
+    frame: Vec<u32>,
+    /// the fractal is rendered with pixels this many times larger on each side, then upscaled,
+    /// to stay responsive on slow machines
+    resolution_divisor: i32,
+    /// the fractal must be recomputed
+    dirty: bool,
+    /// the frame must be put to the window again
+    redraw: bool,
+    show_grid: bool,
+    /// crosshair and coordinate under the mouse
+    show_crosshair: bool,
+    /// only render a region in the middle of the window, to quickly see the effect of the settings
+    preview: bool,
+    /// corners of the rectangle being dragged with the left button
+    selection: Option<((i32, i32), (i32, i32))>,
+    /// clicks draw the orbit of the point instead of doing nothing
+    orbit_mode: bool,
+    /// orbit of the last clicked point, until the next click or key press
+    orbit: Option<Vec<Complex<f64>>>,
+    /// second fractal shown on the other half of the window, to compare settings
+    split: Option<Split>,
+    /// animation of the Julia constant
+    sweep: Sweep,
+    /// the Julia constant is the complex number under the mouse
+    julia_pick: bool,
+    /// navigation being recorded as a tour
+    recording: Option<Recorder>,
+    /// tour being replayed
+    playback: Option<Playback>,
+    /// seeds of the random palettes
+    rng: Rng,
+    /// frame rate while the fractal is being rendered or animated
+    frame_timer: FrameTimer,
+    /// preferences saved when they are changed
+    settings: Settings,
+    mouse: MouseState,
+    /// the quit key was pressed or the window closed, the event loop ends
+    quitting: bool,
+}
+
+impl App {
+    pub fn new(
+        mlx: Mlx,
+        window: MlxWindow,
+        image: MlxImage,
+        mode: Mode,
+        export: ExportConfig,
+        keymap: Keymap,
+        tile_size: Option<i32>,
+    ) -> Self {
+        let (width, height) = (image.width, image.height);
+        Self {
+            mlx,
+            window,
+            image,
+            mode,
+            viewport: mode.default_viewport(width, height),
+            config: RenderConfig::default(),
+            export,
+            keymap,
+            tile_size,
+            job: None,
+            frame: vec![0; (width * height) as usize],
+            resolution_divisor: 1,
+            dirty: true,
+            redraw: true,
+            show_grid: false,
+            show_crosshair: false,
+            preview: false,
+            selection: None,
+            orbit_mode: false,
+            orbit: None,
+            split: None,
+            sweep: Sweep::default(),
+            julia_pick: false,
+            recording: None,
+            playback: None,
+            rng: Rng::new(seconds_since_epoch()),
+            frame_timer: FrameTimer::default(),
+            settings: Settings::default(),
+            mouse: MouseState::default(),
+            quitting: false,
+        }
+    }
+
+    /// Restores the view and settings of a saved session.
+    pub fn restore(&mut self, session: Session) {
+        self.mode = session.fractal;
+        self.viewport = session.viewport;
+        self.config = session.config;
+        self.dirty = true;
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+
+    pub fn set_sweep(&mut self, sweep: Sweep) {
+        self.sweep = sweep;
+    }
+
+    pub fn window(&self) -> &MlxWindow {
+        &self.window
+    }
+
+    fn session(&self) -> Session {
+        Session {
+            fractal: self.mode,
+            viewport: self.viewport,
+            config: self.config,
+        }
+    }
+
+    /// Called on every loop iteration, `elapsed` after the previous one, only does work when something changed.
+    pub fn update(&mut self, elapsed: Duration) {
+        self.sweep.advance(elapsed);
+        // the constant is only updated once the previous one is fully rendered
+        let rendering = self.job.is_some() || self.split.as_ref().is_some_and(|s| s.job.is_some());
+        if self.sweep.running() && !rendering && self.uses_julia_constant() {
+            self.config.julia_constant = self.sweep.constant();
+            self.dirty = true;
+        }
+        if let Some(playback) = &mut self.playback {
+            let over = playback.advance(elapsed);
+            // like the sweep, the view only moves once the previous one is rendered, the last one is always shown
+            if !rendering || over {
+                let (width, height) = (self.image.width, self.image.height);
+                self.viewport = playback.tour().viewport(playback.time(), width, height);
+                self.dirty = true;
+            }
+            if over {
+                self.playback = None;
+            }
+        }
+        if let Some(recorder) = &mut self.recording {
+            recorder.record(&self.viewport);
+        }
+        if self.dirty {
+            let viewport = self.viewport.downscaled(self.resolution_divisor);
+            let size = (viewport.width * viewport.height) as usize;
+            if self.frame.len() != size {
+                self.frame = vec![0; size];
+            }
+            let edited = self.split.as_ref().map(|split| split.edited);
+            let renderer = Renderer::new(self.mode, &viewport, &self.config);
+            let area = render_area(&viewport, self.preview, edited);
+            self.job = Some(TiledRender::new(renderer, self.tile_size, area, true));
+            if let Some(split) = &mut self.split {
+                let renderer = Renderer::new(split.mode, &viewport, &split.config);
+                let area = render_area(&viewport, self.preview, Some(split.edited.other()));
+                split.job = Some(TiledRender::new(renderer, self.tile_size, area, true));
+                if split.frame.len() != size {
+                    split.frame = vec![0; size];
+                }
+            }
+            self.update_title();
+            self.dirty = false;
+        }
+        // the budget is shared with the other side of the split screen
+        let budget = if self.split.is_some() {
+            FRAME_BUDGET / 2
+        } else {
+            FRAME_BUDGET
+        };
+        // the input received while rendering is handled right away
+        let interrupted = || self.mlx.events_pending();
+        if let Some(job) = &mut self.job {
+            if job.step(&mut self.frame, budget, &interrupted) {
+                self.job = None;
+            }
+            self.redraw = true;
+        }
+        if let Some(split) = &mut self.split {
+            if let Some(job) = &mut split.job {
+                if job.step(&mut split.frame, budget, &interrupted) {
+                    split.job = None;
+                }
+                self.redraw = true;
+            }
+        }
+        if self.redraw {
+            self.present();
+            self.redraw = false;
+        }
+    }
+
+    /// Renders the fractal into a new image of the new size of the window, keeping the region height and center.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        if (width, height) == (self.image.width, self.image.height) {
+            return;
+        }
+        match self.mlx.new_image(width, height) {
+            Ok(image) => self.image = image,
+            Err(e) => {
+                eprintln!("cannot resize to {}x{}: {}", width, height, e);
+                return;
+            }
+        }
+        self.viewport = self.viewport.resized(width, height);
+        self.selection = None;
+        self.dirty = true;
+    }
+
+    /// Asks for the frame to be put to the window again, e.g. after an expose event.
+    pub fn request_redraw(&mut self) {
+        self.redraw = true;
+    }
+
+    pub fn key_press(&mut self, keycode: i32, modifiers: Modifiers) {
+        if self.orbit.take().is_some() {
+            self.redraw = true;
+        }
+        match self.keymap.action(keycode) {
+            Some(Action::Quit) => self.quit(),
+            Some(Action::Save) if modifiers.ctrl => match self.session().save(SESSION_FILE) {
+                Ok(()) => println!("session saved to {}", SESSION_FILE),
+                Err(e) => eprintln!("{}", e),
+            },
+            Some(Action::Save) => self.screenshot(modifiers.shift),
+            Some(Action::CycleFractal) => {
+                let step = if modifiers.shift { -1 } else { 1 };
+                self.set_mode(self.mode.cycle(step));
+            }
+            Some(Action::Fractal(index)) => {
+                if let Some(mode) = Mode::all().nth(index) {
+                    self.set_mode(mode);
+                }
+            }
+            Some(Action::PanLeft) => self.pan(-PAN_STEP, 0.0),
+            Some(Action::PanRight) => self.pan(PAN_STEP, 0.0),
+            Some(Action::PanUp) => self.pan(0.0, -PAN_STEP),
+            Some(Action::PanDown) => self.pan(0.0, PAN_STEP),
+            Some(Action::Crosshair) => {
+                self.show_crosshair = !self.show_crosshair;
+                self.redraw = true;
+            }
+            Some(Action::Export) => self.export(),
+            Some(Action::ExportPalette) => self.export_palette(),
+            Some(Action::Tour) if modifiers.shift => {
+                self.playback = match Tour::load(TOUR_FILE) {
+                    Ok(tour) => Some(Playback::new(tour)),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        None
+                    }
+                };
+                self.redraw = true;
+            }
+            Some(Action::Tour) => {
+                match self.recording.take() {
+                    Some(recorder) => match recorder.finish(&self.viewport).save(TOUR_FILE) {
+                        Ok(()) => println!("tour saved to {}", TOUR_FILE),
+                        Err(e) => eprintln!("{}", e),
+                    },
+                    None => self.recording = Some(Recorder::new(&self.viewport)),
+                }
+                self.redraw = true;
+            }
+            Some(Action::Precision) => {
+                self.config.precision = self.config.precision.toggle();
+                self.dirty = true;
+            }
+            Some(Action::Coloring) => {
+                self.config.coloring = self.config.coloring.cycle();
+                self.dirty = true;
+            }
+            Some(Action::Grid) => {
+                self.show_grid = !self.show_grid;
+                self.redraw = true;
+            }
+            Some(Action::Preview) => {
+                self.preview = !self.preview;
+                self.dirty = true;
+            }
+            Some(Action::Perturbation) => {
+                self.config.perturbation = !self.config.perturbation;
+                self.dirty = true;
+            }
+            Some(Action::FewerIterations) => {
+                let iterations = self.config.max_iterations.saturating_sub(ITERATIONS_STEP);
+                self.config.max_iterations = iterations.clamp(MIN_ITERATIONS, MAX_ITERATIONS);
+                self.dirty = true;
+            }
+            Some(Action::MoreIterations) => {
+                let iterations = self.config.max_iterations + ITERATIONS_STEP;
+                self.config.max_iterations = iterations.clamp(MIN_ITERATIONS, MAX_ITERATIONS);
+                self.dirty = true;
+            }
+            Some(Action::FewerPoints) => {
+                self.config.points = (self.config.points / 2).max(MIN_POINTS);
+                self.dirty = true;
+            }
+            Some(Action::MorePoints) => {
+                self.config.points = (self.config.points * 2).min(MAX_POINTS);
+                self.dirty = true;
+            }
+            Some(Action::Shallower) => {
+                self.config.depth = self.config.depth.saturating_sub(1);
+                self.dirty = true;
+            }
+            Some(Action::Deeper) => {
+                self.config.depth = (self.config.depth + 1).min(MAX_DEPTH);
+                self.dirty = true;
+            }
+            Some(Action::AutoIterations) => {
+                self.config.auto_iterations = !self.config.auto_iterations;
+                self.dirty = true;
+            }
+            Some(Action::Sweep) => {
+                self.sweep.toggle();
+                self.redraw = true;
+            }
+            Some(Action::PickJulia) => {
+                self.julia_pick = !self.julia_pick;
+                self.redraw = true;
+            }
+            Some(Action::RandomPalette) => {
+                let palette = if modifiers.shift {
+                    Palette::Default
+                } else {
+                    Palette::random(self.rng.next_u64())
+                };
+                self.set_palette(palette);
+            }
+            Some(Action::CyclePalette) => {
+                let step = if modifiers.shift { -1 } else { 1 };
+                self.set_palette(self.config.palette.cycle(step));
+            }
+            Some(Action::Resolution) => {
+                self.resolution_divisor = match self.resolution_divisor {
+                    MAX_RESOLUTION_DIVISOR => 1,
+                    divisor => divisor * 2,
+                };
+                self.dirty = true;
+            }
+            Some(Action::Antialiasing) => {
+                self.config.supersampling = match self.config.supersampling {
+                    MAX_SUPERSAMPLING => 1,
+                    factor => factor * 2,
+                };
+                self.dirty = true;
+            }
+            Some(Action::Orbit) => {
+                self.orbit_mode = !self.orbit_mode;
+                self.redraw = true;
+            }
+            Some(Action::PaletteOffset) => {
+                let step = if modifiers.shift {
+                    -PALETTE_OFFSET_STEP
+                } else {
+                    PALETTE_OFFSET_STEP
+                };
+                self.config.palette_offset = (self.config.palette_offset + step).rem_euclid(1.0);
+                self.dirty = true;
+            }
+            Some(Action::PaletteContrast) => {
+                let step = if modifiers.shift {
+                    PALETTE_CONTRAST_STEP.recip()
+                } else {
+                    PALETTE_CONTRAST_STEP
+                };
+                let contrast = self.config.palette_contrast * step;
+                self.config.palette_contrast =
+                    contrast.clamp(MIN_PALETTE_CONTRAST, MAX_PALETTE_CONTRAST);
+                self.dirty = true;
+            }
+            Some(Action::BailoutView) => {
+                self.config.bailout_view = !self.config.bailout_view;
+                self.dirty = true;
+            }
+            Some(Action::Split) => {
+                self.split = match self.split {
+                    Some(_) => None,
+                    None => Some(Split {
+                        mode: self.mode,
+                        config: self.config,
+                        job: None,
+                        frame: self.frame.clone(),
+                        edited: self.side_at(self.mouse.x),
+                    }),
+                };
+                self.dirty = true;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn mouse_press(&mut self, button: MouseButton, x: i32, y: i32) {
+        let factor = match button {
+            MouseButton::Left => return self.left_press(x, y),
+            MouseButton::WheelUp => WHEEL_ZOOM.recip(),
+            MouseButton::WheelDown => WHEEL_ZOOM,
+            _ => return,
+        };
+        // the point under the cursor stays in place
+        self.viewport = self.viewport.zoom_at(x as f64, y as f64, factor);
+        self.dirty = true;
+    }
+
+    fn left_press(&mut self, x: i32, y: i32) {
+        if self.button_at(x, y).is_some() {
+            self.mouse.down = true;
+            self.redraw = true;
+        } else {
+            self.selection = Some(((x, y), (x, y)));
+        }
+    }
+
+    pub fn mouse_release(&mut self, button: MouseButton, x: i32, y: i32) {
+        if button != MouseButton::Left {
+            return;
+        }
+        if let Some((from, _)) = self.selection.take() {
+            if (x - from.0).abs() >= MIN_SELECTION && (y - from.1).abs() >= MIN_SELECTION {
+                self.viewport = self.viewport.select(from, (x, y));
+                self.dirty = true;
+            } else if self.orbit_mode {
+                self.click_orbit(x, y);
+            }
+        } else if self.mouse.down {
+            self.mouse.released = true;
+        }
+        self.mouse.down = false;
+        self.redraw = true;
+    }
+
+    pub fn mouse_move(&mut self, x: i32, y: i32) {
+        if let Some((from, _)) = self.selection {
+            self.selection = Some((from, (x, y)));
+            self.redraw = true;
+        }
+        // the hovered button is highlighted
+        let hovered = self.button_at(x, y) != self.button_at(self.mouse.x, self.mouse.y);
+        if hovered || self.show_crosshair {
+            self.redraw = true;
+        }
+        self.mouse.x = x;
+        self.mouse.y = y;
+        if self.julia_pick && self.uses_julia_constant() {
+            let c = self.viewport.pixel_to_complex(x as f64, y as f64);
+            let (re, im) = (
+                c.re.clamp(-PICK_LIMIT, PICK_LIMIT),
+                c.im.clamp(-PICK_LIMIT, PICK_LIMIT),
+            );
+            self.config.julia_constant = Complex::new(re, im);
+            self.dirty = true;
+        }
+        // the keys change the side under the mouse
+        if self
+            .split
+            .as_ref()
+            .is_some_and(|split| split.edited != self.side_at(x))
+        {
+            self.switch_side();
+        }
+    }
+
+    /// Swaps the mode and settings with the ones of the other side of the split screen.
+    fn switch_side(&mut self) {
+        let Some(split) = &mut self.split else {
+            return;
+        };
+        mem::swap(&mut self.mode, &mut split.mode);
+        mem::swap(&mut self.config, &mut split.config);
+        mem::swap(&mut self.job, &mut split.job);
+        mem::swap(&mut self.frame, &mut split.frame);
+        split.edited = split.edited.other();
+        self.orbit = None;
+        self.update_title();
+        self.redraw = true;
+    }
+
+    /// Column of the window where the right side of the split screen starts.
+    fn divider_x(&self) -> i32 {
+        let frame_width = self.viewport.downscaled(self.resolution_divisor).width;
+        divider(frame_width) * self.resolution_divisor
+    }
+
+    /// Side of the split screen under the pixel column `x` of the window.
+    fn side_at(&self, x: i32) -> Side {
+        if x < self.divider_x() {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
+
+    /// Shows the orbit of the point under the pixel `(x, y)`, or hides the one shown.
+    fn click_orbit(&mut self, x: i32, y: i32) {
+        let Mode::EscapeTime(fractal) = self.mode else {
+            return;
+        };
+        self.orbit = match self.orbit {
+            Some(_) => None,
+            None => {
+                let c = self.viewport.pixel_to_complex(x as f64, y as f64);
+                Some(FRACTALS[fractal].orbit(c, &self.config))
+            }
+        };
+    }
+
+    /// Moves the view by fractions of its width and height, see [Viewport::panned].
+    fn pan(&mut self, dx: f64, dy: f64) {
+        self.viewport = self.viewport.panned(dx, dy);
+        self.dirty = true;
+    }
+
+    /// Colors the fractal with `palette`, which is saved in the settings.
+    fn set_palette(&mut self, palette: Palette) {
+        self.config.palette = palette;
+        self.settings.palette = palette;
+        if let Err(e) = self.settings.save() {
+            eprintln!("{}", e);
+        }
+        self.dirty = true;
+    }
+
+    /// Switches to `mode`, showing all of it.
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.orbit = None;
+        self.viewport = mode.default_viewport(self.image.width, self.image.height);
+        self.dirty = true;
+    }
+
+    fn uses_julia_constant(&self) -> bool {
+        self.mode.uses_julia_constant()
+    }
+
+    /// Mode of the switcher button under the pixel `(x, y)`.
+    fn button_at(&self, x: i32, y: i32) -> Option<Mode> {
+        overlay::switcher_buttons(self.image.height)
+            .into_iter()
+            .find(|(rect, _)| rect.contains(x, y))
+            .map(|(_, mode)| mode)
+    }
+
+    /// Writes the image shown in the window, with its overlays but without the text, to a PPM or a BMP file.
+    fn screenshot(&self, bmp: bool) {
+        let extension = if bmp { "bmp" } else { "ppm" };
+        let path = format!("fractol-screenshot-{}.{}", seconds_since_epoch(), extension);
+        let saved = if bmp {
+            self.image.save_bmp(&path)
+        } else {
+            self.image.save_ppm(&path)
+        };
+        match saved {
+            Ok(()) => println!("saved {}", path),
+            Err(e) => eprintln!("{}: {}", path, e),
+        }
+    }
+
+    /// Writes the current view to an antialiased PPM file.
+    fn export(&self) {
+        let size = self
+            .export
+            .size
+            .unwrap_or((self.image.width, self.image.height));
+        let path = format!("fractol-{}.ppm", seconds_since_epoch());
+
+        let viewport = Viewport {
+            width: size.0,
+            height: size.1,
+            ..self.viewport
+        };
+        let pixels = export::render_antialiased(
+            self.mode,
+            &viewport,
+            &self.config,
+            size,
+            &self.export,
+            Some(&mut export::progress_bar(&path)),
+        );
+        match export::write_ppm(&path, size.0 as usize, &pixels) {
+            Ok(()) => println!("exported {}", path),
+            Err(e) => eprintln!("{}: {}", path, e),
+        }
+    }
+
+    /// Writes the palette, with its offset and contrast, as a gradient labeled with its name.
+    fn export_palette(&mut self) {
+        let (width, height) = PALETTE_SWATCH_SIZE;
+        let row: Vec<u32> = (0..width)
+            .map(|x| self.config.palette_color_at(x as f64 / (width - 1) as f64))
+            .collect();
+        let mut pixels = row.repeat(height as usize);
+        match self.label_swatch(&pixels, width, height) {
+            Ok(labeled) => pixels = labeled,
+            Err(e) => eprintln!("the palette swatch is not labeled: {}", e),
+        }
+        // the swatch was drawn over the frame
+        self.redraw = true;
+
+        let path = format!(
+            "fractol-palette-{}-{}.ppm",
+            self.config.palette,
+            seconds_since_epoch()
+        );
+        match export::write_ppm(&path, width as usize, &pixels) {
+            Ok(()) => println!("exported {}", path),
+            Err(e) => eprintln!("{}: {}", path, e),
+        }
+    }
+
+    /// Adds the name of the palette to a swatch.
+    ///
+    /// Only X draws text, so the swatch is put in the corner of the window with its label,
+    /// then read back from the window.
+    fn label_swatch(&self, pixels: &[u32], width: i32, height: i32) -> Result<Vec<u32>, MlxError> {
+        if self.viewport.width < width || self.viewport.height < height {
+            return Err(MlxError::Any(
+                "the window is smaller than the swatch".to_string(),
+            ));
+        }
+        let mut label = self.config.palette.to_string();
+        let (offset, contrast) = (self.config.palette_offset, self.config.palette_contrast);
+        if offset != 0.0 || contrast != 1.0 {
+            label += &format!(" +{:.2} x{:.2}", offset, contrast);
+        }
+
+        let mut swatch = self.mlx.new_image(width, height)?;
+        for (row, swatch_row) in swatch.rows_mut().zip(pixels.chunks(width as usize)) {
+            row.copy_from_slice(swatch_row);
+        }
+        self.mlx.put_image_to_window(&self.window, &swatch, 0, 0);
+        let (white, black) = (colors::WHITE, colors::BLACK);
+        let baseline = (height + LINE_HEIGHT) / 2 - 2;
+        let mut labeled = self
+            .mlx
+            .string_put_outlined(&self.window, 4, baseline, white, black, &label)
+            .and_then(|()| self.mlx.capture_window(&self.window))?;
+        Ok(labeled
+            .rows_mut()
+            .take(height as usize)
+            .flat_map(|row| row[..width as usize].to_vec())
+            .collect())
+    }
+
+    /// Ends the event loop, see [quitting](Self::quitting).
+    pub fn quit(&mut self) {
+        self.quitting = true;
+    }
+
+    /// The event loop should end, main then returns and the window and images are freed.
+    pub fn quitting(&self) -> bool {
+        self.quitting
+    }
+
+    fn present(&mut self) {
+        self.frame_timer.tick();
+        // nearest neighbour upscaling, the left side of the split screen coming from its own frame
+        let divisor = self.resolution_divisor as usize;
+        let frame_width = self.viewport.downscaled(self.resolution_divisor).width;
+        let split_x = self.split.as_ref().map_or(0, |_| divider(frame_width));
+        let (left, right) = match &self.split {
+            Some(split) if split.edited == Side::Left => (&self.frame, &split.frame),
+            Some(split) => (&split.frame, &self.frame),
+            None => (&self.frame, &self.frame),
+        };
+        let (frame_width, split_x) = (frame_width as usize, split_x as usize);
+        for (y, row) in self.image.rows_mut().enumerate() {
+            let start = y / divisor * frame_width;
+            let left_row = &left[start..][..frame_width];
+            let right_row = &right[start..][..frame_width];
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let x = x / divisor;
+                *pixel = if x < split_x {
+                    left_row[x]
+                } else {
+                    right_row[x]
+                };
+            }
+        }
+        if self.split.is_some() {
+            overlay::draw_divider(&self.image, self.divider_x());
+        }
+        if self.show_grid {
+            overlay::draw_grid(&self.image, &self.viewport);
+        }
+        if let Some((from, to)) = self.selection {
+            overlay::draw_selection(&self.image, from, to);
+        }
+        if self.preview {
+            let region = preview_region(self.image.width, self.image.height);
+            overlay::draw_preview(&self.image, region);
+        }
+        if self.show_crosshair {
+            overlay::draw_crosshair(&self.image, self.mouse.x, self.mouse.y);
+        }
+        if let Some(orbit) = &self.orbit {
+            overlay::draw_orbit(&self.image, &self.viewport, orbit);
+        }
+
+        let mut ui = Ui::new(&self.image, self.mouse);
+        let mut clicked = None;
+        for (rect, mode) in overlay::switcher_buttons(self.image.height) {
+            if ui.button(rect, mode.name()) {
+                clicked = Some(mode);
+            }
+        }
+
+        self.mlx.present_synced(&self.window, &self.image, 0, 0);
+
+        if self.show_grid {
+            overlay::draw_grid_labels(&self.mlx, &self.window, &self.viewport);
+        }
+        overlay::draw_hud(&self.mlx, &self.window, &self.hud());
+        ui.draw_labels(&self.mlx, &self.window);
+
+        self.mouse.released = false;
+        if let Some(mode) = clicked {
+            self.set_mode(mode);
+        }
+    }
+
+    fn update_title(&self) {
+        let zoom = self.mode.zoom(&self.viewport);
+        let title = format!("Fractol - {} (zoom x{:.1})", self.mode.name(), zoom);
+        if let Err(e) = self.mlx.set_window_title(&self.window, &title) {
+            eprintln!("{}", e);
+        }
+    }
+
+    fn hud(&self) -> Vec<String> {
+        let mut lines = vec![
+            self.mode.name().to_string(),
+            self.config.precision.to_string(),
+            format!("{} coloring", self.config.coloring),
+        ];
+        if self.config.palette != Palette::Default {
+            lines.push(format!("palette {}", self.config.palette));
+        }
+        let (offset, contrast) = (self.config.palette_offset, self.config.palette_contrast);
+        if offset != 0.0 || contrast != 1.0 {
+            lines.push(format!(
+                "palette offset {:.2}, contrast x{:.2}",
+                offset, contrast
+            ));
+        }
+        if self.config.auto_iterations && self.mode.pixel_by_pixel() {
+            let auto = self.config.iterations_at(self.mode.zoom(&self.viewport));
+            lines.push(format!(
+                "{} iterations, auto {}",
+                self.config.max_iterations, auto
+            ));
+        } else if self.mode.pixel_by_pixel() {
+            lines.push(format!("{} iterations", self.config.max_iterations));
+        }
+        if self.frame_timer.fps().is_some() {
+            lines.push(self.frame_timer.to_string());
+        }
+        if self.sweep.running() && self.uses_julia_constant() {
+            let c = self.config.julia_constant;
+            lines.push(format!("sweep c = {:.4} {:+.4}i", c.re, c.im));
+        } else if self.julia_pick && self.uses_julia_constant() {
+            let c = self.config.julia_constant;
+            lines.push(format!("pick c = {:.4} {:+.4}i", c.re, c.im));
+        }
+        if self.mode == Mode::Koch {
+            lines.push(format!("depth {}", self.config.depth));
+        }
+        if self.mode == Mode::Lyapunov {
+            lines.push(format!("sequence {}", self.config.sequence));
+        }
+        if self.config.perturbation {
+            lines.push("perturbation".to_string());
+        }
+        if self.preview {
+            lines.push("preview".to_string());
+        }
+        if let Some(recorder) = &self.recording {
+            lines.push(format!(
+                "recording tour, {} keyframes",
+                recorder.keyframes()
+            ));
+        }
+        if let Some(playback) = &self.playback {
+            let (time, duration) = (playback.time(), playback.tour().duration());
+            lines.push(format!("playing tour {:.1}s / {:.1}s", time, duration));
+        }
+        if self.config.bailout_view {
+            lines.push("bailout view: navy escaped, white capped, red not finite".to_string());
+        }
+        if let Some(split) = &self.split {
+            let side = match split.edited {
+                Side::Left => "left",
+                Side::Right => "right",
+            };
+            lines.push(format!("split, editing the {} side", side));
+        }
+        if self.resolution_divisor > 1 {
+            lines.push(format!("1/{} resolution", self.resolution_divisor));
+        }
+        if self.config.supersampling > 1 {
+            lines.push(format!("{}x antialiasing", self.config.supersampling));
+        }
+        if self.orbit_mode {
+            let radius = self.config.escape_radius;
+            let state = match self.orbit.as_ref().and_then(|orbit| orbit.last()) {
+                Some(z) if z.norm() >= radius => "escaped",
+                Some(_) => "bounded",
+                None => "click a point",
+            };
+            lines.push(format!("orbit ({})", state));
+        }
+        if self.show_crosshair {
+            let (x, y) = (self.mouse.x, self.mouse.y);
+            lines.push(overlay::coordinate(&self.viewport, x, y));
+        }
+        lines
+    }
+}
+
+/// Column of a frame `width` pixels wide where the right side of the split screen starts.
+fn divider(width: i32) -> i32 {
+    width / 2
+}
+
+/// Part of the frame of `viewport` rendered for `side` of the split screen, with a single fractal when `None`.
+///
+/// Returns `None` when the whole frame is rendered.
+fn render_area(viewport: &Viewport, preview: bool, side: Option<Side>) -> Option<Tile> {
+    let (width, height) = (viewport.width, viewport.height);
+    let preview = preview.then(|| preview_region(width, height));
+    let Some(side) = side else {
+        return preview;
+    };
+    let divider = divider(width);
+    let half = match side {
+        Side::Left => Tile {
+            x: 0,
+            y: 0,
+            width: divider,
+            height,
+        },
+        Side::Right => Tile {
+            x: divider,
+            y: 0,
+            width: width - divider,
+            height,
+        },
+    };
+    Some(preview.map_or(half, |preview| preview.intersection(&half)))
+}
+
+/// Region in the middle of a `width` by `height` frame rendered in preview mode.
+fn preview_region(width: i32, height: i32) -> Tile {
+    let (region_width, region_height) = (width / PREVIEW_FRACTION, height / PREVIEW_FRACTION);
+    Tile {
+        x: (width - region_width) / 2,
+        y: (height - region_height) / 2,
+        width: region_width,
+        height: region_height,
+    }
+}
+
+fn seconds_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
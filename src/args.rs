@@ -0,0 +1,217 @@
+use crate::export::ExportConfig;
+use crate::fractal::Mode;
+use crate::lyapunov::Sequence;
+use num_complex::Complex;
+
+/// Command line options.
+#[derive(Default)]
+pub struct Args {
+    /// fractal to draw, given by name
+    pub fractal: Option<Mode>,
+    /// `c` of the Julia set, given after its name
+    pub julia_constant: Option<Complex<f64>>,
+    /// session file to restore at startup
+    pub load: Option<String>,
+    /// how images are exported
+    pub export: ExportConfig,
+    /// key bindings file
+    pub keys: Option<String>,
+    /// size of the window
+    pub size: Option<(i32, i32)>,
+    /// width and height of the window, overriding the ones of `size`
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// center the window on the screen
+    pub center: bool,
+    /// side of the render tiles, picked from the cost of the pixels when `None`
+    pub tile_size: Option<i32>,
+    /// session file of the last view of a zoom movie, and its number of frames
+    pub zoom_sequence: Option<(String, u32)>,
+    /// radius of the circle followed by the Julia constant when animated
+    pub sweep_radius: Option<f64>,
+    /// radians per second of the Julia constant when animated
+    pub sweep_speed: Option<f64>,
+    /// number of windows side by side showing one wide view
+    pub windows: Option<i32>,
+    /// order of the rates of the Lyapunov fractal
+    pub sequence: Option<Sequence>,
+    /// tour file rendered as a movie, and its frames per second
+    pub tour: Option<(String, u32)>,
+}
+
+pub const USAGE: &str =
+    "usage: fractol [<fractal name> [<re> <im>]] [--load <session file>] [--keys <keymap file>]
+               [--size <W>x<H>] [--width <W>] [--height <H>] [--center]
+               [--export-supersampling <N>] [--export-size <W>x<H>]
+               [--export-adaptive <threshold>] [--export-jitter <seed>] [--tile-size <N>]
+               [--zoom-sequence <end session file> <frames>] [--tour <tour file> <fps>]
+               [--sweep-radius <R>] [--sweep-speed <radians per second>] [--windows <N>]
+               [--sequence <letters A and B>]";
+
+/// Parses a `<width>x<height>` size.
+fn parse_size(size: &str) -> Option<(i32, i32)> {
+    let (width, height) = size.split_once('x')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    if width > 0 && height > 0 {
+        Some((width, height))
+    } else {
+        None
+    }
+}
+
+impl Args {
+    /// Parses the arguments, without the program name.
+    ///
+    /// The name of a fractal using a Julia constant can be followed by its real and imaginary parts.
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.peekable();
+        let mut parsed = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--load" => {
+                    let path = args.next().ok_or("--load expects a file")?;
+                    parsed.load = Some(path);
+                }
+                "--keys" => {
+                    let path = args.next().ok_or("--keys expects a file")?;
+                    parsed.keys = Some(path);
+                }
+                "--size" => {
+                    parsed.size = Some(
+                        args.next()
+                            .as_deref()
+                            .and_then(parse_size)
+                            .ok_or("--size expects <width>x<height>")?,
+                    );
+                }
+                "--width" => {
+                    parsed.width = Some(
+                        args.next()
+                            .and_then(|n| n.parse().ok())
+                            .filter(|&n| n > 0)
+                            .ok_or("--width expects a positive number")?,
+                    );
+                }
+                "--height" => {
+                    parsed.height = Some(
+                        args.next()
+                            .and_then(|n| n.parse().ok())
+                            .filter(|&n| n > 0)
+                            .ok_or("--height expects a positive number")?,
+                    );
+                }
+                "--center" => parsed.center = true,
+                "--export-supersampling" => {
+                    parsed.export.supersampling = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|&n| n > 0)
+                        .ok_or("--export-supersampling expects a positive number")?;
+                }
+                "--export-size" => {
+                    parsed.export.size = Some(
+                        args.next()
+                            .as_deref()
+                            .and_then(parse_size)
+                            .ok_or("--export-size expects <width>x<height>")?,
+                    );
+                }
+                "--export-adaptive" => {
+                    parsed.export.adaptive = true;
+                    parsed.export.adaptive_threshold = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("--export-adaptive expects a threshold")?;
+                }
+                "--export-jitter" => {
+                    parsed.export.jitter = Some(
+                        args.next()
+                            .and_then(|n| n.parse().ok())
+                            .ok_or("--export-jitter expects a seed")?,
+                    );
+                }
+                "--tile-size" => {
+                    parsed.tile_size = Some(
+                        args.next()
+                            .and_then(|n| n.parse().ok())
+                            .filter(|&n| n > 0)
+                            .ok_or("--tile-size expects a positive number")?,
+                    );
+                }
+                "--zoom-sequence" => {
+                    let path = args.next().ok_or("--zoom-sequence expects a file")?;
+                    let frames = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|&n| n > 0)
+                        .ok_or("--zoom-sequence expects a positive number of frames")?;
+                    parsed.zoom_sequence = Some((path, frames));
+                }
+                "--tour" => {
+                    let path = args.next().ok_or("--tour expects a file")?;
+                    let fps = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|&n| n > 0)
+                        .ok_or("--tour expects a positive number of frames per second")?;
+                    parsed.tour = Some((path, fps));
+                }
+                "--sweep-radius" => {
+                    parsed.sweep_radius = Some(
+                        args.next()
+                            .and_then(|n| n.parse().ok())
+                            .filter(|&n: &f64| n > 0.0)
+                            .ok_or("--sweep-radius expects a positive number")?,
+                    );
+                }
+                "--sweep-speed" => {
+                    parsed.sweep_speed = Some(
+                        args.next()
+                            .and_then(|n| n.parse().ok())
+                            .filter(|n: &f64| n.is_finite())
+                            .ok_or("--sweep-speed expects a number")?,
+                    );
+                }
+                "--windows" => {
+                    parsed.windows = Some(
+                        args.next()
+                            .and_then(|n| n.parse().ok())
+                            .filter(|&n| n > 0)
+                            .ok_or("--windows expects a positive number")?,
+                    );
+                }
+                "--sequence" => {
+                    parsed.sequence = Some(
+                        args.next()
+                            .and_then(|s| s.parse().ok())
+                            .ok_or("--sequence expects up to 32 letters A and B")?,
+                    );
+                }
+                name => match Mode::from_name(name) {
+                    Some(mode) if parsed.fractal.is_none() => {
+                        parsed.fractal = Some(mode);
+                        let re = args.peek().and_then(|n| n.parse::<f64>().ok());
+                        if let Some(re) = re.filter(|_| mode.uses_julia_constant()) {
+                            args.next();
+                            let im = args
+                                .next()
+                                .and_then(|n| n.parse::<f64>().ok())
+                                .filter(|n| n.is_finite() && re.is_finite())
+                                .ok_or_else(|| format!("{} expects <re> <im>", name))?;
+                            parsed.julia_constant = Some(Complex::new(re, im));
+                        }
+                    }
+                    _ => return Err(format!("unknown argument `{}`", arg)),
+                },
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Size of the window, `default` for what is not given.
+    pub fn window_size(&self, default: (i32, i32)) -> (i32, i32) {
+        let (width, height) = self.size.unwrap_or(default);
+        (self.width.unwrap_or(width), self.height.unwrap_or(height))
+    }
+}
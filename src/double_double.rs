@@ -0,0 +1,77 @@
+//! Double-double arithmetic, for the center of views zoomed in past what `f64` can tell apart.
+//!
+//! The complex numbers of the view are `Complex<DoubleDouble>`, the pixels being small `f64` offsets from them.
+
+use num_complex::Complex;
+
+/// Unevaluated sum of two `f64`, for about 106 bits of precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+impl DoubleDouble {
+    pub fn new(x: f64) -> Self {
+        Self { hi: x, lo: 0.0 }
+    }
+
+    /// Renormalizes `a + b` where `|a| >= |b|`.
+    fn quick_two_sum(a: f64, b: f64) -> Self {
+        let hi = a + b;
+        Self {
+            hi,
+            lo: b - (hi - a),
+        }
+    }
+
+    pub fn add(self, other: Self) -> Self {
+        let hi = self.hi + other.hi;
+        let b = hi - self.hi;
+        let error = (self.hi - (hi - b)) + (other.hi - b);
+        Self::quick_two_sum(hi, error + self.lo + other.lo)
+    }
+
+    pub fn sub(self, other: Self) -> Self {
+        self.add(Self {
+            hi: -other.hi,
+            lo: -other.lo,
+        })
+    }
+
+    pub fn mul(self, other: Self) -> Self {
+        let hi = self.hi * other.hi;
+        let error = self.hi.mul_add(other.hi, -hi);
+        Self::quick_two_sum(hi, error + self.hi * other.lo + self.lo * other.hi)
+    }
+
+    pub fn double(self) -> Self {
+        Self {
+            hi: 2.0 * self.hi,
+            lo: 2.0 * self.lo,
+        }
+    }
+}
+
+/// `c` with double-double parts.
+pub fn complex(c: Complex<f64>) -> Complex<DoubleDouble> {
+    Complex::new(DoubleDouble::new(c.re), DoubleDouble::new(c.im))
+}
+
+/// `c` rounded to `f64` parts.
+pub fn round(c: Complex<DoubleDouble>) -> Complex<f64> {
+    Complex::new(c.re.hi, c.im.hi)
+}
+
+/// `c + offset`, keeping the precision of `c`.
+pub fn offset(c: Complex<DoubleDouble>, offset: Complex<f64>) -> Complex<DoubleDouble> {
+    Complex::new(
+        c.re.add(DoubleDouble::new(offset.re)),
+        c.im.add(DoubleDouble::new(offset.im)),
+    )
+}
+
+/// `a - b` rounded to `f64`, which keeps its precision when they are close.
+pub fn difference(a: Complex<DoubleDouble>, b: Complex<DoubleDouble>) -> Complex<f64> {
+    round(Complex::new(a.re.sub(b.re), a.im.sub(b.im)))
+}
@@ -0,0 +1,431 @@
+use crate::fractal::Mode;
+use crate::render::{self, ColorMode, RenderConfig, Renderer};
+use crate::rng::Rng;
+use crate::viewport::Viewport;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+pub const SUPERSAMPLING: u32 = 4;
+/// Largest difference of a channel between two neighbouring pixels left alone by the adaptive antialiasing.
+pub const ADAPTIVE_THRESHOLD: u32 = 16;
+
+/// How images are exported.
+#[derive(Clone, Copy, Debug)]
+pub struct ExportConfig {
+    /// each exported pixel averages `supersampling * supersampling` rendered ones
+    pub supersampling: u32,
+    /// size of the exported image, the window size when `None`
+    pub size: Option<(i32, i32)>,
+    /// only supersample the pixels differing from their neighbours, see [render_adaptive]
+    pub adaptive: bool,
+    pub adaptive_threshold: u32,
+    /// seed of the random places of the samples within each pixel, on a regular grid when `None`,
+    /// see [supersample]
+    pub jitter: Option<u64>,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            supersampling: SUPERSAMPLING,
+            size: None,
+            adaptive: false,
+            adaptive_threshold: ADAPTIVE_THRESHOLD,
+            jitter: None,
+        }
+    }
+}
+
+/// Renders the view at `size`, antialiased as set in `export`.
+pub fn render_antialiased(
+    mode: Mode,
+    viewport: &Viewport,
+    config: &RenderConfig,
+    size: (i32, i32),
+    export: &ExportConfig,
+    progress: Option<&mut dyn FnMut(f32)>,
+) -> Vec<u32> {
+    let supersampling = export.supersampling;
+    match export.jitter {
+        _ if export.adaptive => render_adaptive(mode, viewport, config, size, export, progress),
+        Some(seed) => render_jittered(mode, viewport, config, size, supersampling, seed, progress),
+        None => render_supersampled(mode, viewport, config, size, supersampling, progress),
+    }
+}
+
+/// Renders the view at `width` by `height`, supersampled to smooth its edges.
+///
+/// The fractal is rendered offscreen at `supersampling` times the size, then downsampled.
+pub fn render_supersampled(
+    mode: Mode,
+    viewport: &Viewport,
+    config: &RenderConfig,
+    (width, height): (i32, i32),
+    supersampling: u32,
+    progress: Option<&mut dyn FnMut(f32)>,
+) -> Vec<u32> {
+    let factor = supersampling.max(1) as i32;
+    let viewport = Viewport {
+        width: width * factor,
+        height: height * factor,
+        ..*viewport
+    };
+    let config = RenderConfig {
+        // keeps the same density of points per exported pixel
+        points: config.points.saturating_mul(supersampling * supersampling),
+        // the frame is already rendered larger
+        supersampling: 1,
+        ..*config
+    };
+
+    let mut frame = vec![0; (viewport.width * viewport.height) as usize];
+    render::render_fractal(mode, &viewport, &config, &mut frame, progress);
+    downsample(&frame, viewport.width as usize, factor as usize)
+}
+
+/// Renders the view at `width` by `height`, with `supersampling * supersampling` samples
+/// at random places within each pixel, picked from `seed`, see [supersample].
+///
+/// The fractals that are not escape-time ones, and the [histogram](ColorMode::Histogram) coloring
+/// whose colors depend on the whole frame, are supersampled on a regular grid.
+pub fn render_jittered(
+    mode: Mode,
+    viewport: &Viewport,
+    config: &RenderConfig,
+    (width, height): (i32, i32),
+    supersampling: u32,
+    seed: u64,
+    mut progress: Option<&mut dyn FnMut(f32)>,
+) -> Vec<u32> {
+    let size = (width, height);
+    if !mode.pixel_by_pixel() || config.coloring == ColorMode::Histogram {
+        return render_supersampled(mode, viewport, config, size, supersampling, progress);
+    }
+
+    let viewport = Viewport {
+        width,
+        height,
+        ..*viewport
+    };
+    let renderer = Renderer::new(mode, &viewport, config);
+    let mut rng = Rng::new(seed);
+    let (width, height) = (width as usize, height as usize);
+    let mut frame = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            frame.push(supersample(
+                &renderer,
+                (x, y),
+                supersampling,
+                Some(&mut rng),
+            ));
+        }
+        render::report(&mut progress, y + 1, height);
+    }
+    frame
+}
+
+/// Renders the view at `width` by `height`, only supersampling the edges.
+///
+/// The view is first rendered with a sample per pixel. The pixels with a channel differing by more than
+/// the threshold from a neighbour are then replaced with the average of `supersampling * supersampling` samples,
+/// which gives most of the quality of [render_supersampled] for the cost of the edges only.
+/// The fractals that are not computed pixel by pixel, and the [histogram](ColorMode::Histogram) coloring
+/// whose colors depend on the whole frame, are supersampled uniformly.
+pub fn render_adaptive(
+    mode: Mode,
+    viewport: &Viewport,
+    config: &RenderConfig,
+    (width, height): (i32, i32),
+    export: &ExportConfig,
+    mut progress: Option<&mut dyn FnMut(f32)>,
+) -> Vec<u32> {
+    let (supersampling, threshold) = (export.supersampling, export.adaptive_threshold);
+    let size = (width, height);
+    if !mode.pixel_by_pixel() || config.coloring == ColorMode::Histogram {
+        return render_supersampled(mode, viewport, config, size, supersampling, progress);
+    }
+
+    // the first pass is reported as the first half of the progress
+    let viewport = Viewport {
+        width,
+        height,
+        ..*viewport
+    };
+    let mut frame = vec![0; (width * height) as usize];
+    let mut first_pass = |done: f32| {
+        if let Some(progress) = &mut progress {
+            progress(done / 2.0);
+        }
+    };
+    render::render_fractal(mode, &viewport, config, &mut frame, Some(&mut first_pass));
+
+    let (width, height) = (width as usize, height as usize);
+    let mut edges = vec![false; frame.len()];
+    for i in 0..frame.len() {
+        let neighbours = [(i % width + 1 < width).then(|| i + 1), Some(i + width)];
+        for j in neighbours
+            .into_iter()
+            .flatten()
+            .filter(|&j| j < frame.len())
+        {
+            if color_distance(frame[i], frame[j]) > threshold {
+                edges[i] = true;
+                edges[j] = true;
+            }
+        }
+    }
+
+    let renderer = Renderer::new(mode, &viewport, config);
+    let mut rng = export.jitter.map(Rng::new);
+    for y in 0..height {
+        for x in (0..width).filter(|&x| edges[y * width + x]) {
+            frame[y * width + x] = supersample(&renderer, (x, y), supersampling, rng.as_mut());
+        }
+        render::report(&mut progress, height + y + 1, 2 * height);
+    }
+    frame
+}
+
+/// Color of the pixel `(x, y)`, the average of `supersampling * supersampling` samples,
+/// one in each cell of a grid dividing the pixel.
+///
+/// The samples are at the corner of their cell, or at a random place within it when `rng` is given:
+/// regular grids can show moiré patterns on fine repeating details, jittered samples trade them for noise.
+fn supersample(
+    renderer: &Renderer,
+    (x, y): (usize, usize),
+    supersampling: u32,
+    mut rng: Option<&mut Rng>,
+) -> u32 {
+    let factor = supersampling.max(1);
+    let mut samples = Vec::with_capacity((factor * factor) as usize);
+    for sy in 0..factor {
+        for sx in 0..factor {
+            let (jx, jy) = match &mut rng {
+                Some(rng) => (rng.next_f64(), rng.next_f64()),
+                None => (0.0, 0.0),
+            };
+            let sample_x = x as f64 + (sx as f64 + jx) / factor as f64;
+            let sample_y = y as f64 + (sy as f64 + jy) / factor as f64;
+            samples.extend(renderer.pixel(sample_x, sample_y));
+        }
+    }
+    average(&samples)
+}
+
+/// Largest difference between the channels of two `0xRRGGBB` colors.
+pub fn color_distance(a: u32, b: u32) -> u32 {
+    [16, 8, 0]
+        .into_iter()
+        .map(|shift| ((a >> shift & 0xff) as i32 - (b >> shift & 0xff) as i32).unsigned_abs())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Average of `0xRRGGBB` colors.
+fn average(colors: &[u32]) -> u32 {
+    let mut sum = [0u32; 3];
+    for color in colors {
+        sum[0] += color >> 16 & 0xff;
+        sum[1] += color >> 8 & 0xff;
+        sum[2] += color & 0xff;
+    }
+    let count = colors.len().max(1) as u32;
+    (sum[0] / count) << 16 | (sum[1] / count) << 8 | (sum[2] / count)
+}
+
+/// Progress callback drawing a bar on stderr, the line is ended once the render is done.
+pub fn progress_bar(label: &str) -> impl FnMut(f32) + '_ {
+    let mut drawn = None;
+    move |done| {
+        let filled = (done * PROGRESS_BAR_WIDTH as f32) as usize;
+        let percent = (done * 100.0) as u32;
+        // redrawing the same bar for every row would flood the terminal
+        if drawn == Some(percent) {
+            return;
+        }
+        drawn = Some(percent);
+        eprint!(
+            "\r{} [{}{}] {:3}%",
+            label,
+            "#".repeat(filled),
+            " ".repeat(PROGRESS_BAR_WIDTH - filled),
+            percent
+        );
+        if percent >= 100 {
+            eprintln!();
+        }
+    }
+}
+
+/// Shrinks an image `width` pixels wide by `factor`, each pixel being the average of a `factor` by `factor` block.
+pub fn downsample(pixels: &[u32], width: usize, factor: usize) -> Vec<u32> {
+    let height = pixels.len() / width;
+    let (out_width, out_height) = (width / factor, height / factor);
+    let mut out = Vec::with_capacity(out_width * out_height);
+
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let mut sum = [0u32; 3];
+            for sy in 0..factor {
+                let row = (y * factor + sy) * width + x * factor;
+                for color in &pixels[row..row + factor] {
+                    sum[0] += color >> 16 & 0xff;
+                    sum[1] += color >> 8 & 0xff;
+                    sum[2] += color & 0xff;
+                }
+            }
+            let count = (factor * factor) as u32;
+            out.push((sum[0] / count) << 16 | (sum[1] / count) << 8 | (sum[2] / count));
+        }
+    }
+    out
+}
+
+/// Writes `0xRRGGBB` pixels as a binary PPM (P6) file.
+pub fn write_ppm(path: &str, width: usize, pixels: &[u32]) -> io::Result<()> {
+    let height = pixels.len() / width;
+    let mut file = BufWriter::new(File::create(path)?);
+
+    write!(file, "P6\n{} {}\n255\n", width, height)?;
+    for color in pixels {
+        file.write_all(&[(color >> 16) as u8, (color >> 8) as u8, *color as u8])?;
+    }
+    file.flush()
+}
+
+/// Writes `0xRRGGBB` pixels as an RGB PNG file.
+///
+/// The image data is stored without compression, which keeps the encoder small
+/// at the cost of files as large as a PPM.
+pub fn write_png(path: &str, width: usize, pixels: &[u32]) -> io::Result<()> {
+    let height = pixels.len() / width;
+    let mut data = Vec::with_capacity(height * (width * 3 + 1));
+    for row in pixels.chunks(width) {
+        // no filter
+        data.push(0);
+        for color in row {
+            data.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, *color as u8]);
+        }
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, default compression, filter and interlacing methods
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(b"\x89PNG\r\n\x1a\n")?;
+    write_chunk(&mut file, b"IHDR", &header)?;
+    write_chunk(&mut file, b"IDAT", &zlib_stored(&data))?;
+    write_chunk(&mut file, b"IEND", &[])?;
+    file.flush()
+}
+
+/// Writes a PNG chunk, its length, type, data and checksum.
+fn write_chunk(file: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    file.write_all(&(data.len() as u32).to_be_bytes())?;
+    file.write_all(kind)?;
+    file.write_all(data)?;
+    file.write_all(&crc32(kind.iter().chain(data)).to_be_bytes())
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    /// `cargo test --release -- --ignored --nocapture antialiasing_benchmark`
+    #[test]
+    #[ignore]
+    fn antialiasing_benchmark() {
+        let mode = Mode::from_name("mandelbrot").unwrap();
+        let size = (800, 600);
+        let viewport = mode.default_viewport(size.0, size.1);
+        let config = RenderConfig::default();
+        let export = ExportConfig {
+            adaptive: true,
+            ..ExportConfig::default()
+        };
+
+        let start = Instant::now();
+        let uniform = black_box(render_supersampled(
+            mode,
+            &viewport,
+            &config,
+            size,
+            export.supersampling,
+            None,
+        ));
+        println!("uniform: {:?}", start.elapsed());
+        let start = Instant::now();
+        let adaptive = black_box(render_adaptive(
+            mode, &viewport, &config, size, &export, None,
+        ));
+        println!("adaptive: {:?}", start.elapsed());
+
+        let difference: u32 = uniform
+            .iter()
+            .zip(&adaptive)
+            .map(|(&a, &b)| {
+                [16, 8, 0]
+                    .into_iter()
+                    .map(|shift| (a >> shift & 0xff).abs_diff(b >> shift & 0xff))
+                    .sum::<u32>()
+            })
+            .sum();
+        println!(
+            "mean channel difference: {:.2}",
+            difference as f64 / (3 * uniform.len()) as f64
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use crate::double_double;
+use crate::lyapunov;
+use crate::newton;
+use crate::render::{Precision, RenderConfig};
+use crate::viewport::Viewport;
+use num_complex::Complex;
+use num_traits::Float;
+
+/// What the iterations of a point ended on, for the coloring modes to pick from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Escape {
+    /// iterations left when the point escaped, 0 if it never did
+    pub count: u32,
+    /// last value of `z`
+    pub z: Complex<f64>,
+    /// derivative of `z` with respect to the point, along the real axis for the folded fractals,
+    /// only computed when [Tracking::derivative] is set
+    pub dz: Complex<f64>,
+    /// smallest `|z|` of the orbit past the starting value (an orbit trap at the origin),
+    /// only computed when [Tracking::trap] is set
+    pub trap: f64,
+}
+
+/// Values computed along the iterations on top of the count, only when a coloring mode needs them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tracking {
+    pub derivative: bool,
+    pub trap: bool,
+}
+
+/// A fractal colored by how fast each point escapes under iteration.
+pub trait EscapeTime: Sync {
+    fn name(&self) -> &'static str;
+
+    /// View showing the whole fractal.
+    fn default_viewport(&self, width: i32, height: i32) -> Viewport {
+        Viewport::new(width, height)
+    }
+
+    /// Iterates the point `c`, tracking what the coloring mode of `config` needs.
+    fn escape(&self, c: Complex<f64>, config: &RenderConfig) -> Escape;
+
+    /// Every value taken by `z` while iterating the point `c`, from the first one until it escapes.
+    fn orbit(&self, c: Complex<f64>, config: &RenderConfig) -> Vec<Complex<f64>>;
+
+    /// Whether the fractal is symmetric about the real axis, so half of a centered view can be mirrored.
+    fn symmetric(&self) -> bool {
+        false
+    }
+
+    /// Whether the fractal depends on [RenderConfig::julia_constant].
+    fn uses_julia_constant(&self) -> bool {
+        false
+    }
+
+    /// Whether the fractal can be rendered by the [perturbation](crate::perturbation) renderer.
+    fn perturbation(&self) -> bool {
+        false
+    }
+}
+
+/// All the escape-time fractals, in the order they are cycled through.
+pub const FRACTALS: &[&dyn EscapeTime] = &[&Julia, &Mandelbrot, &Tricorn, &BurningShip];
+
+fn single(z: Complex<f64>) -> Complex<f32> {
+    Complex::new(z.re as f32, z.im as f32)
+}
+
+/// Change made to `z` before it is squared, giving the variants of `z² + c`, see [quadratic].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Fold {
+    /// leaves `z` as it is, for `z² + c`
+    Identity,
+    /// complex conjugate, for the [Tricorn]
+    Conjugate,
+    /// absolute values of the parts, for the [BurningShip]
+    Absolute,
+}
+
+impl Fold {
+    fn apply<T: Float>(self, z: Complex<T>) -> Complex<T> {
+        match self {
+            Fold::Identity => z,
+            Fold::Conjugate => z.conj(),
+            Fold::Absolute => Complex::new(z.re.abs(), z.im.abs()),
+        }
+    }
+
+    /// Change of the folded `z` for a small change `dz` of `z`.
+    ///
+    /// The folds are not holomorphic, so this is the change along `dz` only,
+    /// e.g. the absolute value flips the parts of `dz` where the parts of `z` are negative.
+    fn tangent<T: Float>(self, z: Complex<T>, dz: Complex<T>) -> Complex<T> {
+        match self {
+            Fold::Identity => dz,
+            Fold::Conjugate => dz.conj(),
+            Fold::Absolute => Complex::new(dz.re * z.re.signum(), dz.im * z.im.signum()),
+        }
+    }
+}
+
+/// Iterates `fold(z)² + c` from `z` until it escapes, see [EscapeTime::escape].
+///
+/// `c_derivative` is the derivative of `c` with respect to the point: 1 when `c` is the point, 0 when it is a constant.
+/// The derivative is taken along the real axis, which is the derivative itself without a fold,
+/// as `z² + c` is holomorphic.
+fn quadratic<T: Float>(
+    mut z: Complex<T>,
+    c: Complex<T>,
+    c_derivative: T,
+    fold: Fold,
+    config: &RenderConfig,
+) -> Escape {
+    let tracking = config.coloring.tracking();
+    let radius = T::from(config.escape_radius).unwrap_or_else(T::max_value);
+    let radius_sqr = radius * radius;
+    let two = T::one() + T::one();
+    let mut dz = Complex::new(T::one() - c_derivative, T::zero());
+    let mut trap = T::infinity();
+    let mut i = config.max_iterations;
+
+    while i > 0 && z.norm_sqr() < radius_sqr {
+        let folded = fold.apply(z);
+        if tracking.derivative {
+            dz = fold.tangent(z, dz) * folded * two + c_derivative;
+        }
+        z = folded * folded + c;
+        // the starting value is left out, it is the origin for most fractals
+        if tracking.trap {
+            trap = trap.min(z.norm());
+        }
+        i -= 1;
+    }
+
+    let double =
+        |z: Complex<T>| Complex::new(z.re.to_f64().unwrap_or(0.0), z.im.to_f64().unwrap_or(0.0));
+    Escape {
+        count: if z.norm_sqr() < radius_sqr { 0 } else { i },
+        z: double(z),
+        dz: double(dz),
+        trap: trap.to_f64().unwrap_or(f64::INFINITY),
+    }
+}
+
+/// Values of `z` while iterating `fold(z)² + c` from `z`, in `f64` whatever the precision.
+fn quadratic_orbit(
+    mut z: Complex<f64>,
+    c: Complex<f64>,
+    fold: Fold,
+    config: &RenderConfig,
+) -> Vec<Complex<f64>> {
+    let radius_sqr = config.escape_radius * config.escape_radius;
+    let mut orbit = vec![z];
+    while orbit.len() <= config.max_iterations as usize && z.norm_sqr() < radius_sqr {
+        z = fold.apply(z);
+        z = z * z + c;
+        orbit.push(z);
+    }
+    orbit
+}
+
+pub struct Julia;
+
+impl EscapeTime for Julia {
+    fn name(&self) -> &'static str {
+        "julia"
+    }
+
+    fn escape(&self, z: Complex<f64>, config: &RenderConfig) -> Escape {
+        match config.precision {
+            Precision::Single => quadratic(
+                single(z),
+                single(config.julia_constant),
+                0.0,
+                Fold::Identity,
+                config,
+            ),
+            Precision::Double => quadratic(z, config.julia_constant, 0.0, Fold::Identity, config),
+        }
+    }
+
+    fn orbit(&self, z: Complex<f64>, config: &RenderConfig) -> Vec<Complex<f64>> {
+        quadratic_orbit(z, config.julia_constant, Fold::Identity, config)
+    }
+
+    fn uses_julia_constant(&self) -> bool {
+        true
+    }
+}
+
+pub struct Mandelbrot;
+
+impl EscapeTime for Mandelbrot {
+    fn name(&self) -> &'static str {
+        "mandelbrot"
+    }
+
+    fn default_viewport(&self, width: i32, height: i32) -> Viewport {
+        Viewport {
+            center: double_double::complex(Complex::new(-0.5, 0.0)),
+            ..Viewport::new(width, height)
+        }
+    }
+
+    fn escape(&self, c: Complex<f64>, config: &RenderConfig) -> Escape {
+        match config.precision {
+            Precision::Single => quadratic(
+                Complex::new(0.0, 0.0),
+                single(c),
+                1.0,
+                Fold::Identity,
+                config,
+            ),
+            Precision::Double => quadratic(Complex::new(0.0, 0.0), c, 1.0, Fold::Identity, config),
+        }
+    }
+
+    fn orbit(&self, c: Complex<f64>, config: &RenderConfig) -> Vec<Complex<f64>> {
+        quadratic_orbit(Complex::new(0.0, 0.0), c, Fold::Identity, config)
+    }
+
+    fn symmetric(&self) -> bool {
+        true
+    }
+
+    fn perturbation(&self) -> bool {
+        true
+    }
+}
+
+/// The Mandelbrot set of the complex conjugate, iterating `conj(z)² + c`, also called the Mandelbar set.
+pub struct Tricorn;
+
+impl EscapeTime for Tricorn {
+    fn name(&self) -> &'static str {
+        "tricorn"
+    }
+
+    fn default_viewport(&self, width: i32, height: i32) -> Viewport {
+        Viewport {
+            center: double_double::complex(Complex::new(-0.3, 0.0)),
+            scale: 3.2,
+            ..Viewport::new(width, height)
+        }
+    }
+
+    fn escape(&self, c: Complex<f64>, config: &RenderConfig) -> Escape {
+        match config.precision {
+            Precision::Single => quadratic(
+                Complex::new(0.0, 0.0),
+                single(c),
+                1.0,
+                Fold::Conjugate,
+                config,
+            ),
+            Precision::Double => quadratic(Complex::new(0.0, 0.0), c, 1.0, Fold::Conjugate, config),
+        }
+    }
+
+    fn orbit(&self, c: Complex<f64>, config: &RenderConfig) -> Vec<Complex<f64>> {
+        quadratic_orbit(Complex::new(0.0, 0.0), c, Fold::Conjugate, config)
+    }
+
+    fn symmetric(&self) -> bool {
+        true
+    }
+}
+
+/// The Mandelbrot set of the absolute values of the parts of `z`, iterating `(|Re z| + i|Im z|)² + c`.
+///
+/// The ship is usually drawn with the imaginary axis going down, which the [Viewport] already does,
+/// so it is the right way up without flipping anything.
+pub struct BurningShip;
+
+impl EscapeTime for BurningShip {
+    fn name(&self) -> &'static str {
+        "burning_ship"
+    }
+
+    fn default_viewport(&self, width: i32, height: i32) -> Viewport {
+        Viewport {
+            center: double_double::complex(Complex::new(-0.4, -0.5)),
+            scale: 3.0,
+            ..Viewport::new(width, height)
+        }
+    }
+
+    fn escape(&self, c: Complex<f64>, config: &RenderConfig) -> Escape {
+        match config.precision {
+            Precision::Single => quadratic(
+                Complex::new(0.0, 0.0),
+                single(c),
+                1.0,
+                Fold::Absolute,
+                config,
+            ),
+            Precision::Double => quadratic(Complex::new(0.0, 0.0), c, 1.0, Fold::Absolute, config),
+        }
+    }
+
+    fn orbit(&self, c: Complex<f64>, config: &RenderConfig) -> Vec<Complex<f64>> {
+        quadratic_orbit(Complex::new(0.0, 0.0), c, Fold::Absolute, config)
+    }
+}
+
+/// What is drawn in the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// index of a fractal in [FRACTALS]
+    EscapeTime(usize),
+    /// Sierpinski triangle plotted with the chaos game
+    Sierpinski,
+    /// Koch snowflake drawn with lines
+    Koch,
+    /// stability of the logistic map, see [lyapunov]
+    Lyapunov,
+    /// basins of the roots of `z³ - 1` under Newton's method, see [newton]
+    Newton,
+}
+
+impl Mode {
+    /// Every mode, escape-time fractals first.
+    pub fn all() -> impl Iterator<Item = Mode> {
+        (0..FRACTALS.len()).map(Mode::EscapeTime).chain([
+            Mode::Sierpinski,
+            Mode::Koch,
+            Mode::Lyapunov,
+            Mode::Newton,
+        ])
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::EscapeTime(i) => FRACTALS[i].name(),
+            Mode::Sierpinski => "sierpinski",
+            Mode::Koch => "koch",
+            Mode::Lyapunov => "lyapunov",
+            Mode::Newton => "newton",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().find(|mode| mode.name() == name)
+    }
+
+    pub fn default_viewport(self, width: i32, height: i32) -> Viewport {
+        match self {
+            Mode::EscapeTime(i) => FRACTALS[i].default_viewport(width, height),
+            Mode::Sierpinski | Mode::Koch => Viewport::new(width, height),
+            Mode::Lyapunov => lyapunov::default_viewport(width, height),
+            Mode::Newton => newton::default_viewport(width, height),
+        }
+    }
+
+    /// Whether each pixel is computed on its own, so the frame can be split into tiles and supersampled.
+    ///
+    /// The chaos game and the lines are drawn on the whole frame at once.
+    pub fn pixel_by_pixel(self) -> bool {
+        matches!(self, Mode::EscapeTime(_) | Mode::Lyapunov | Mode::Newton)
+    }
+
+    /// Whether the mode depends on [RenderConfig::julia_constant].
+    pub fn uses_julia_constant(self) -> bool {
+        matches!(self, Mode::EscapeTime(fractal) if FRACTALS[fractal].uses_julia_constant())
+    }
+
+    /// How many times `viewport` is zoomed in from the default view.
+    pub fn zoom(self, viewport: &Viewport) -> f64 {
+        self.default_viewport(viewport.width, viewport.height).scale / viewport.scale
+    }
+
+    /// The mode after this one in [Mode::all], `step` can be negative to go back.
+    pub fn cycle(self, step: isize) -> Self {
+        let modes: Vec<Mode> = Self::all().collect();
+        let current = modes.iter().position(|&mode| mode == self).unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(modes.len() as isize);
+        modes[next as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::ColorMode;
+
+    #[test]
+    fn burning_ship_hull_does_not_escape() {
+        let config = RenderConfig::default();
+        let escape = BurningShip.escape(Complex::new(-1.75, -0.02), &config);
+        assert_eq!(escape.count, 0);
+    }
+
+    #[test]
+    fn folded_derivative_matches_finite_difference() {
+        let config = RenderConfig {
+            max_iterations: 4,
+            escape_radius: 1e10,
+            coloring: ColorMode::Distance,
+            ..RenderConfig::default()
+        };
+        let (c, h) = (Complex::new(0.3, 0.2), 1e-7);
+        for fractal in [&Mandelbrot as &dyn EscapeTime, &Tricorn, &BurningShip] {
+            let escape = fractal.escape(c, &config);
+            let moved = fractal.escape(c + h, &config);
+            let difference = (moved.z - escape.z) / h;
+            assert!((difference - escape.dz).norm() < 1e-4, "{}", fractal.name());
+        }
+    }
+
+    #[test]
+    fn burning_ship_outside_escapes_quickly() {
+        let config = RenderConfig::default();
+        let escape = BurningShip.escape(Complex::new(2.0, 2.0), &config);
+        assert!(
+            escape.count >= config.max_iterations - 2,
+            "count {}",
+            escape.count
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Golden image tests: frames rendered headless, compared with references in `testdata/`.
+//!
+//! The references are PNG files, written by [export::write_png](crate::export::write_png).
+//! Run the tests with `FRACTOL_BLESS=1` to write them again after an intended change of the rendering.
+
+use crate::export;
+use crate::fractal::Mode;
+use crate::render::{self, ColorMode, RenderConfig};
+use std::env;
+use std::fs;
+
+/// Size of the golden frames, small enough for the references to stay a few kilobytes.
+const SIZE: (i32, i32) = (64, 48);
+/// Differing pixels listed in the failure message.
+const REPORTED_PIXELS: usize = 8;
+
+/// Pixels of a PNG file written by [export::write_png], with its width, `None` when it is not one.
+///
+/// Only what the encoder writes is decoded: 8 bits RGB, unfiltered rows and uncompressed deflate blocks.
+fn read_png(bytes: &[u8]) -> Option<(usize, Vec<u32>)> {
+    let be_u32 = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    if bytes.get(..8)? != b"\x89PNG\r\n\x1a\n" {
+        return None;
+    }
+    let (mut width, mut stream) = (0, Vec::new());
+    let mut at = 8;
+    while at < bytes.len() {
+        let len = be_u32(at)? as usize;
+        let data = bytes.get(at + 8..at + 8 + len)?;
+        match bytes.get(at + 4..at + 8)? {
+            b"IHDR" if data[8..] == [8, 2, 0, 0, 0] => width = be_u32(at + 8)? as usize,
+            b"IHDR" => return None,
+            b"IDAT" => stream.extend_from_slice(data),
+            _ => (),
+        }
+        // length, type, data and checksum
+        at += len + 12;
+    }
+
+    // skips the zlib header, then each block has a flag byte and its length twice
+    let mut data = Vec::new();
+    let mut at = 2;
+    loop {
+        let last = *stream.get(at)? & 1 == 1;
+        let len = u16::from_le_bytes(stream.get(at + 1..at + 3)?.try_into().ok()?) as usize;
+        data.extend_from_slice(stream.get(at + 5..at + 5 + len)?);
+        at += len + 5;
+        if last {
+            break;
+        }
+    }
+
+    let mut pixels = Vec::new();
+    for row in data.chunks(width * 3 + 1) {
+        if row[0] != 0 {
+            return None;
+        }
+        pixels.extend(
+            row[1..]
+                .chunks(3)
+                .map(|rgb| (rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32),
+        );
+    }
+    Some((width, pixels))
+}
+
+/// Positions of the pixels of `actual` differing from `expected` by more than `tolerance` on a channel,
+/// both frames being `width` pixels wide.
+pub fn differing_pixels(
+    actual: &[u32],
+    expected: &[u32],
+    width: usize,
+    tolerance: u32,
+) -> Vec<(usize, usize)> {
+    actual
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|&(_, (&a, &b))| export::color_distance(a, b) > tolerance)
+        .map(|(i, _)| (i % width, i / width))
+        .collect()
+}
+
+/// Asserts that `frame` matches the reference `testdata/<name>.png` within `tolerance` on each channel,
+/// reporting how many pixels differ and where.
+pub fn assert_matches_reference(name: &str, width: usize, frame: &[u32], tolerance: u32) {
+    let path = format!("{}/testdata/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+    if env::var_os("FRACTOL_BLESS").is_some() {
+        export::write_png(&path, width, frame).unwrap();
+        return;
+    }
+    let bytes = fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let (expected_width, expected) =
+        read_png(&bytes).unwrap_or_else(|| panic!("{}: not a PNG file from write_png", path));
+    assert_eq!(
+        (expected_width, expected.len()),
+        (width, frame.len()),
+        "{}: size differs",
+        path
+    );
+
+    let differing = differing_pixels(frame, &expected, width, tolerance);
+    assert!(
+        differing.is_empty(),
+        "{}: {} pixels differ by more than {}, starting at {:?}",
+        path,
+        differing.len(),
+        tolerance,
+        &differing[..differing.len().min(REPORTED_PIXELS)]
+    );
+}
+
+/// Renders the default view of the fractal named `name` at [SIZE].
+fn render(name: &str, config: &RenderConfig) -> Vec<u32> {
+    let mode = Mode::from_name(name).unwrap();
+    let viewport = mode.default_viewport(SIZE.0, SIZE.1);
+    let mut frame = vec![0; (SIZE.0 * SIZE.1) as usize];
+    render::render_fractal(mode, &viewport, config, &mut frame, None);
+    frame
+}
+
+#[test]
+fn differing_pixels_are_located() {
+    let expected = [0x000000, 0x102030, 0xffffff, 0x808080];
+    let actual = [0x000001, 0x102030, 0xfffff0, 0x808080];
+    assert_eq!(differing_pixels(&actual, &expected, 2, 1), vec![(0, 1)]);
+    assert!(differing_pixels(&actual, &expected, 2, 15).is_empty());
+}
+
+#[test]
+fn mandelbrot_matches_reference() {
+    let frame = render("mandelbrot", &RenderConfig::default());
+    assert_matches_reference("mandelbrot", SIZE.0 as usize, &frame, 2);
+}
+
+#[test]
+fn julia_smooth_matches_reference() {
+    let config = RenderConfig {
+        coloring: ColorMode::Smooth,
+        ..RenderConfig::default()
+    };
+    let frame = render("julia", &config);
+    assert_matches_reference("julia-smooth", SIZE.0 as usize, &frame, 2);
+}
+
+#[test]
+fn newton_matches_reference() {
+    let frame = render("newton", &RenderConfig::default());
+    assert_matches_reference("newton", SIZE.0 as usize, &frame, 2);
+}
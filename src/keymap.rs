@@ -0,0 +1,199 @@
+//! Key bindings, which can be changed in a flat TOML file mapping actions to keycodes:
+//!
+//! ```toml
+//! quit = "113, 65307"  # several keys are separated by commas
+//! grid = 103
+//! ```
+//!
+//! Keycodes are X11 keysyms, the `xev` command prints them.
+//! Actions missing from the file keep their default keys.
+
+use crate::toml;
+use mlx::Key;
+use std::fs;
+
+pub const KEYMAP_FILE: &str = "fractol-keys.toml";
+
+/// Something a key does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    /// saves a PPM screenshot of the window, a BMP one with shift held, the session with control held
+    Save,
+    /// goes back with shift held
+    CycleFractal,
+    /// switches to the fractal at this index in [Mode::all](crate::fractal::Mode::all)
+    Fractal(usize),
+    /// moves the view by a step of [PAN_STEP](crate::app::PAN_STEP)
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    Export,
+    Crosshair,
+    Precision,
+    Coloring,
+    Grid,
+    Preview,
+    Perturbation,
+    FewerIterations,
+    MoreIterations,
+    FewerPoints,
+    MorePoints,
+    Shallower,
+    Deeper,
+    /// clicks draw the orbit of the point
+    Orbit,
+    /// raise the iterations as the view is zoomed in
+    AutoIterations,
+    /// starts or pauses the animation of the Julia constant
+    Sweep,
+    /// the Julia constant follows the mouse
+    PickJulia,
+    /// generates a new random palette, goes back to the default one with shift held
+    RandomPalette,
+    /// goes to the next preset palette, the previous one with shift held
+    CyclePalette,
+    /// renders at a lower resolution, cycling through the divisors
+    Resolution,
+    /// supersamples the pixels to smooth the edges, cycling through the factors
+    Antialiasing,
+    /// compares two settings side by side, the keys changing the side under the mouse
+    Split,
+    /// debugging view of how the iterations ended
+    BailoutView,
+    /// rotates the palette, backwards with shift held
+    PaletteOffset,
+    /// stretches the palette, compresses it with shift held
+    PaletteContrast,
+    /// writes the palette as a gradient image
+    ExportPalette,
+    /// starts or stops recording the navigation as a tour, replays the recorded tour with shift held
+    Tour,
+}
+
+/// Name of each action in the file, and its default keys.
+const ACTIONS: [(Action, &str, &[Key]); 43] = [
+    (Action::Quit, "quit", &[Key::Q, Key::Escape]),
+    (Action::Save, "save", &[Key::S]),
+    (Action::CycleFractal, "cycle_fractal", &[Key::Tab]),
+    (Action::Fractal(0), "fractal_1", &[Key::Num1]),
+    (Action::Fractal(1), "fractal_2", &[Key::Num2]),
+    (Action::Fractal(2), "fractal_3", &[Key::Num3]),
+    (Action::Fractal(3), "fractal_4", &[Key::Num4]),
+    (Action::Fractal(4), "fractal_5", &[Key::Num5]),
+    (Action::Fractal(5), "fractal_6", &[Key::Num6]),
+    (Action::Fractal(6), "fractal_7", &[Key::Num7]),
+    (Action::Fractal(7), "fractal_8", &[Key::Num8]),
+    (Action::Fractal(8), "fractal_9", &[Key::Num9]),
+    (Action::PanLeft, "pan_left", &[Key::Left]),
+    (Action::PanRight, "pan_right", &[Key::Right]),
+    (Action::PanUp, "pan_up", &[Key::Up]),
+    (Action::PanDown, "pan_down", &[Key::Down]),
+    (Action::Export, "export", &[Key::E]),
+    (Action::Crosshair, "crosshair", &[Key::C]),
+    (Action::Precision, "precision", &[Key::F]),
+    (Action::Coloring, "coloring", &[Key::M]),
+    (Action::Grid, "grid", &[Key::G]),
+    (Action::Preview, "preview", &[Key::V]),
+    (Action::Perturbation, "perturbation", &[Key::P]),
+    (
+        Action::FewerIterations,
+        "fewer_iterations",
+        &[Key::Minus, Key::KeypadSubtract],
+    ),
+    (
+        Action::MoreIterations,
+        "more_iterations",
+        &[Key::Plus, Key::Equal, Key::KeypadAdd],
+    ),
+    (Action::FewerPoints, "fewer_points", &[Key::BracketLeft]),
+    (Action::MorePoints, "more_points", &[Key::BracketRight]),
+    (Action::Shallower, "shallower", &[Key::Comma]),
+    (Action::Deeper, "deeper", &[Key::Period]),
+    (Action::Orbit, "orbit", &[Key::O]),
+    (Action::AutoIterations, "auto_iterations", &[Key::I]),
+    (Action::Sweep, "sweep", &[Key::J]),
+    (Action::PickJulia, "pick_julia", &[Key::Z]),
+    (Action::RandomPalette, "random_palette", &[Key::R]),
+    (Action::CyclePalette, "cycle_palette", &[Key::N]),
+    (Action::Resolution, "resolution", &[Key::L]),
+    (Action::Antialiasing, "antialiasing", &[Key::A]),
+    (Action::Split, "split", &[Key::X]),
+    (Action::BailoutView, "bailout_view", &[Key::B]),
+    (Action::PaletteOffset, "palette_offset", &[Key::H]),
+    (Action::PaletteContrast, "palette_contrast", &[Key::K]),
+    (Action::ExportPalette, "export_palette", &[Key::W]),
+    (Action::Tour, "tour", &[Key::T]),
+];
+
+/// Position of `action` in [ACTIONS].
+fn order(action: Action) -> usize {
+    ACTIONS
+        .iter()
+        .position(|&(a, _, _)| a == action)
+        .unwrap_or(ACTIONS.len())
+}
+
+/// Action bound to each keycode.
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: Vec<(i32, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|&(action, _, keys)| keys.iter().map(move |&key| (key.keycode(), action)))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Reads a keymap file, unknown actions and keys bound twice are reported on stderr.
+    ///
+    /// A key bound twice does the first of its actions, in the order of the file format.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let table = toml::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let mut keymap = Self::default();
+
+        for name in table.keys() {
+            let Some(&(action, _, _)) = ACTIONS.iter().find(|(_, n, _)| *n == name) else {
+                eprintln!("{}: unknown action `{}` ignored", path, name);
+                continue;
+            };
+            let value = table.get(name).unwrap_or_default();
+            let keys = value
+                .split(',')
+                .map(|key| key.trim().parse())
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|_| format!("{}: invalid keycodes `{}` for `{}`", path, value, name))?;
+
+            keymap.bindings.retain(|&(_, a)| a != action);
+            keymap
+                .bindings
+                .extend(keys.into_iter().map(|key| (key, action)));
+        }
+
+        keymap.bindings.sort_by_key(|&(_, action)| order(action));
+        for (i, &(key, action)) in keymap.bindings.iter().enumerate() {
+            if let Some((_, other)) = keymap.bindings[..i].iter().find(|(k, _)| *k == key) {
+                eprintln!(
+                    "{}: key {} bound to both {:?} and {:?}",
+                    path, key, other, action
+                );
+            }
+        }
+        Ok(keymap)
+    }
+
+    pub fn action(&self, keycode: i32) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(key, _)| *key == keycode)
+            .map(|&(_, action)| action)
+    }
+}
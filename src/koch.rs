@@ -0,0 +1,90 @@
+use crate::render::RenderConfig;
+use crate::viewport::Viewport;
+use mlx::colors;
+use num_complex::Complex;
+
+const COLOR: u32 = colors::WHITE;
+/// distance from the center to the corners of the starting triangle
+const RADIUS: f64 = 0.95;
+
+/// Draws the Koch snowflake into `frame`.
+///
+/// Each side of an equilateral triangle is split in three, the middle third being replaced by the two other sides
+/// of an equilateral triangle pointing outwards, [depth](RenderConfig::depth) times.
+pub fn draw(viewport: &Viewport, config: &RenderConfig, frame: &mut [u32]) {
+    frame.fill(0);
+    // clockwise on screen, so the bumps point outwards
+    let corners: Vec<Complex<f64>> = [-90.0f64, 30.0, 150.0]
+        .iter()
+        .map(|angle| Complex::from_polar(RADIUS, angle.to_radians()))
+        .collect();
+
+    for side in 0..corners.len() {
+        let (a, b) = (corners[side], corners[(side + 1) % corners.len()]);
+        subdivide(a, b, config.depth, &mut |a, b| {
+            draw_line(
+                viewport,
+                frame,
+                viewport.complex_to_pixel(a),
+                viewport.complex_to_pixel(b),
+            );
+        });
+    }
+}
+
+/// Calls `segment` with each segment of the Koch curve from `a` to `b`.
+fn subdivide(
+    a: Complex<f64>,
+    b: Complex<f64>,
+    depth: u32,
+    segment: &mut impl FnMut(Complex<f64>, Complex<f64>),
+) {
+    if depth == 0 {
+        return segment(a, b);
+    }
+    let third = (b - a) / 3.0;
+    let (p1, p3) = (a + third, a + third * 2.0);
+    // the middle third turned by -60°, to the outside of a clockwise triangle
+    let peak = p1 + third * Complex::from_polar(1.0, -60f64.to_radians());
+
+    subdivide(a, p1, depth - 1, segment);
+    subdivide(p1, peak, depth - 1, segment);
+    subdivide(peak, p3, depth - 1, segment);
+    subdivide(p3, b, depth - 1, segment);
+}
+
+/// Draws a line between two pixels using Bresenham's algorithm, like [MlxImage::draw_line](mlx::MlxImage::draw_line).
+fn draw_line(viewport: &Viewport, frame: &mut [u32], from: (f64, f64), to: (f64, f64)) {
+    let (x0, y0) = (from.0.round() as i64, from.1.round() as i64);
+    let (x1, y1) = (to.0.round() as i64, to.1.round() as i64);
+    let (width, height) = (viewport.width as i64, viewport.height as i64);
+    // zoomed in, most segments are far outside of the frame
+    let outside = |a: i64, b: i64, size: i64| (a < 0 && b < 0) || (a >= size && b >= size);
+    if outside(x0, x1, width) || outside(y0, y1, height) {
+        return;
+    }
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+
+    loop {
+        if x >= 0 && x < width && y >= 0 && y < height {
+            frame[(y * width + x) as usize] = COLOR;
+        }
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
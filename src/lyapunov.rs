@@ -0,0 +1,137 @@
+//! Lyapunov fractal, the stability of the logistic map `x → r x (1 - x)` when its rate `r`
+//! alternates between two values `a` and `b` following a sequence of letters.
+//!
+//! Each pixel is a pair of rates, `a` along the real axis and `b` along the imaginary axis.
+//! The map only stays within `[0, 1]` for rates between 0 and 4, the interesting part being
+//! between 2 and 4, where the default view is. The finest details are between 3.4 and 4:
+//! "Zircon Zity" is the sequence `BBBBBBAAAAAA` with `a` in `[3.4, 4]` and `b` in `[2.5, 3.4]`.
+
+use crate::double_double;
+use crate::render::RenderConfig;
+use crate::viewport::Viewport;
+use mlx::colors;
+use num_complex::Complex;
+use std::fmt;
+use std::str::FromStr;
+
+/// Iterations skipped before the exponent is measured, while the map settles.
+const SETTLING_ITERATIONS: u32 = 50;
+/// Color of the chaotic points closest to stability, the more chaotic ones fading to black.
+const CHAOS_COLOR: u32 = colors::NAVY;
+
+/// Order in which the rates `a` and `b` are used, repeated over the iterations.
+///
+/// Written as a string of `A` and `B`, up to [Sequence::MAX_LEN] letters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sequence {
+    /// bit `i` is set when the letter `i` is a `B`
+    bits: u32,
+    len: u32,
+}
+
+impl Sequence {
+    pub const MAX_LEN: u32 = u32::BITS;
+
+    /// Rate used at the iteration `n`.
+    fn rate(&self, n: u32, a: f64, b: f64) -> f64 {
+        if self.bits >> (n % self.len) & 1 == 0 {
+            a
+        } else {
+            b
+        }
+    }
+}
+
+impl Default for Sequence {
+    /// `AABAB`
+    fn default() -> Self {
+        Self {
+            bits: 0b10100,
+            len: 5,
+        }
+    }
+}
+
+impl fmt::Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0..self.len {
+            write!(f, "{}", if self.bits >> i & 1 == 0 { 'A' } else { 'B' })?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Sequence {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > Self::MAX_LEN as usize {
+            return Err(());
+        }
+        let mut bits = 0;
+        for (i, letter) in s.chars().enumerate() {
+            match letter {
+                'A' | 'a' => {}
+                'B' | 'b' => bits |= 1 << i,
+                _ => return Err(()),
+            }
+        }
+        Ok(Self {
+            bits,
+            len: s.len() as u32,
+        })
+    }
+}
+
+/// Lyapunov exponent of the logistic map with the rates `a` and `b`, over `iterations`.
+///
+/// It is the average of `ln |r (1 - 2x)|`, the log of the derivative of the map along the orbit:
+/// negative when nearby orbits converge, so the map settles on a cycle, positive when they diverge
+/// and the map is chaotic. It is not finite for rates sending the orbit out of `[0, 1]`.
+pub fn exponent(a: f64, b: f64, sequence: Sequence, iterations: u32) -> f64 {
+    let mut x = 0.5;
+    for n in 0..SETTLING_ITERATIONS {
+        let r = sequence.rate(n, a, b);
+        x = r * x * (1.0 - x);
+    }
+
+    // the logs are only taken when the product of the derivatives would leave the range of `f64`
+    let (mut sum, mut product) = (0.0, 1.0f64);
+    for n in SETTLING_ITERATIONS..SETTLING_ITERATIONS + iterations {
+        let r = sequence.rate(n, a, b);
+        product *= (r * (1.0 - 2.0 * x)).abs();
+        x = r * x * (1.0 - x);
+        if !(1e-100..1e100).contains(&product) {
+            sum += product.ln();
+            product = 1.0;
+        }
+    }
+    (sum + product.ln()) / iterations.max(1) as f64
+}
+
+/// Color of the point `c` of the view, the rates `a` and `b` being its real and imaginary parts.
+///
+/// The stable points go along the palette as their exponent goes down from 0, at `1 - e^λ`.
+/// The chaotic ones are [CHAOS_COLOR], fading as the exponent grows, and black when it is not finite.
+pub fn color(c: Complex<f64>, config: &RenderConfig) -> u32 {
+    let lambda = exponent(c.re, c.im, config.sequence, config.max_iterations);
+    if lambda < 0.0 {
+        return config.palette_color_at(1.0 - lambda.exp());
+    }
+
+    let brightness = (-lambda).exp();
+    let channel = |shift: u32| {
+        let value = (CHAOS_COLOR >> shift & 0xff) as f64 * brightness;
+        // NaN is cast to 0
+        (value as u32) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
+
+/// View of the rates between 2 and 4.
+pub fn default_viewport(width: i32, height: i32) -> Viewport {
+    Viewport {
+        center: double_double::complex(Complex::new(3.0, 3.0)),
+        ..Viewport::new(width, height)
+    }
+}
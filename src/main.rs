@@ -1,83 +1,229 @@
-use mlx::{Mlx, MlxError};
-use num_complex::Complex;
-use std::process;
+mod app;
+mod args;
+mod double_double;
+mod export;
+mod fractal;
+#[cfg(test)]
+mod golden;
+mod keymap;
+mod koch;
+mod lyapunov;
+mod newton;
+mod overlay;
+mod palette;
+mod perturbation;
+mod render;
+mod rng;
+mod session;
+mod settings;
+mod sierpinski;
+mod sweep;
+mod timer;
+mod toml;
+mod tour;
+mod viewport;
+mod wall;
+mod zoom;
 
-const MAX_ITERATIONS: u32 = 110;
-const JULIA_CONSTANT: Complex<f32> = Complex::new(-0.9, 0.27015);
+use app::App;
+use args::{Args, USAGE};
+use fractal::Mode;
+use keymap::{Keymap, KEYMAP_FILE};
+use mlx::Mlx;
+use render::RenderConfig;
+use session::Session;
+use settings::Settings;
+use std::cell::RefCell;
+use std::env;
+use std::path::Path;
+use std::process;
+use std::rc::Rc;
+use sweep::{Sweep, SWEEP_RADIUS, SWEEP_SPEED};
+use tour::Tour;
+use wall::Wall;
+use zoom::ZoomSequence;
 
-fn julia(x: i32, y: i32, image: &mlx::MlxImage) -> u32 {
-    let inner_height = image.height as f32;
-    let inner_width = image.width as f32;
-    let inner_y = y as f32;
-    let inner_x = x as f32;
+fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(1);
+    });
 
-    let mut zx = 3.0 * (inner_x - 0.5 * inner_width) / (inner_width);
-    let mut zy = 2.0 * (inner_y - 0.5 * inner_height) / (inner_height);
+    let settings = Settings::load();
+    let (width, height) = args.window_size(settings.size);
+    let mode = args.fractal.unwrap_or(Mode::EscapeTime(0));
+    // the loaded session, or the default view
+    let mut start = match args.load {
+        Some(path) => Session::load(&path, width, height).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => Session {
+            fractal: mode,
+            viewport: mode.default_viewport(width, height),
+            config: RenderConfig {
+                palette: settings.palette,
+                ..RenderConfig::default()
+            },
+        },
+    };
+    start.config.threads = settings.threads;
+    if let Some(sequence) = args.sequence {
+        start.config.sequence = sequence;
+    }
+    if let Some(c) = args.julia_constant {
+        start.config.julia_constant = c;
+    }
 
-    let mut i = MAX_ITERATIONS;
+    // the zoom movies are rendered without a window
+    if let Some((path, frames)) = args.zoom_sequence {
+        let sequence = Session::load(&path, width, height).map(|end| ZoomSequence {
+            mode: start.fractal,
+            from: start.viewport,
+            to: end.viewport,
+            frames,
+        });
+        if let Err(e) = sequence.and_then(|sequence| sequence.render(&start.config, &args.export)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    if let Some((path, fps)) = args.tour {
+        let (mode, config) = (start.fractal, &start.config);
+        let tour = Tour::load(&path);
+        if let Err(e) =
+            tour.and_then(|tour| tour.render(mode, config, &args.export, (width, height), fps))
+        {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
 
-    while zx * zx + zy * zy < 4.0 && i > 1 {
-        let tmp = zx * zx - zy * zy + JULIA_CONSTANT.re;
-        zy = 2.0 * zx * zy + JULIA_CONSTANT.im;
-        zx = tmp;
-        i -= 1;
+    let mlx = Mlx::new().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let (screen_width, screen_height) = mlx.get_screen_size();
+    if width > screen_width || height > screen_height {
+        eprintln!(
+            "the window cannot be larger than the {}x{} screen",
+            screen_width, screen_height
+        );
+        process::exit(1);
     }
 
-    let r = (i << 3) as u8;
-    let g = (i << 5) as u8;
-    let b = (i * 4) as u8;
-    let color = (r as u32) << 16 | (g as u32) << 8 | b as u32;
-    color
-}
+    // the default keymap file is optional, unlike the one given on the command line
+    let keys = args.keys.or_else(|| {
+        Path::new(KEYMAP_FILE)
+            .exists()
+            .then(|| KEYMAP_FILE.to_string())
+    });
+    let keymap = keys.map_or_else(Keymap::default, |path| {
+        Keymap::load(&path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        })
+    });
 
-fn main() {
-    let mlx = Mlx::new().unwrap();
+    if let Some(count) = args.windows {
+        let (mode, config) = (start.fractal, &start.config);
+        let wall = Wall::new(mlx, mode, &start.viewport, config, count, (width, height));
+        if let Err(e) = wall.and_then(|wall| wall.run(keymap).map_err(|e| e.to_string())) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
 
-    let width = 1080;
-    let height = 720;
-    let window = mlx.new_window(width, height, "Fractol").unwrap();
+    let window = mlx
+        .new_window_resizable(width, height, "Fractol")
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+    if args.center {
+        mlx.move_window(
+            &window,
+            (screen_width - width) / 2,
+            (screen_height - height) / 2,
+        );
+    }
 
-    let image = mlx.new_image(width, height).unwrap();
+    let image = mlx.new_image(width, height).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
 
     println!("{}, {}", image.size_line, image.bits_per_pixel);
 
-    mlx.loop_hook(
-        move |_| {
-            for y in 0..height {
-                for x in 0..width {
-                    let color = julia(x, y, &image);
-                    image.pixel_put(x, y, color);
-                }
-            }
-            mlx.put_image_to_window(&window, &image, 0, 0);
-        },
+    let app = Rc::new(RefCell::new(App::new(
+        mlx.clone(),
+        window,
+        image,
+        start.fractal,
+        args.export,
+        keymap,
+        args.tile_size,
+    )));
+    app.borrow_mut().restore(start);
+    app.borrow_mut().set_settings(settings);
+    app.borrow_mut().set_sweep(Sweep::new(
+        args.sweep_radius.unwrap_or(SWEEP_RADIUS),
+        args.sweep_speed.unwrap_or(SWEEP_SPEED),
+    ));
+
+    let state = app.clone();
+    mlx.loop_hook_timed(move |elapsed, _| state.borrow_mut().update(elapsed), &());
+
+    // the window belongs to the app, it is only borrowed to register the hooks
+    let app_ref = app.borrow();
+    let window = app_ref.window();
+
+    let state = app.clone();
+    window.expose_hook(move |_| state.borrow_mut().request_redraw(), &());
+
+    let state = app.clone();
+    window.resize_hook(
+        move |width, height, _| state.borrow_mut().resize(width, height),
+        &(),
+    );
+
+    let state = app.clone();
+    window.close_hook(move |_| state.borrow_mut().quit(), &());
+
+    let state = app.clone();
+    window.mouse_hook_typed(
+        move |button, x, y, _| state.borrow_mut().mouse_press(button, x, y),
+        &(),
+    );
+
+    let state = app.clone();
+    window.mouse_release_hook(
+        move |button, x, y, _| state.borrow_mut().mouse_release(button.into(), x, y),
         &(),
     );
 
-    // DestroyNotify
-    window.hook(17, 0, move |_| {
-        mlx.destroy_image(&image);
-        mlx.destroy_window(&window);
-        mlx.destroy();
-        process::exit(0);
-    }, &());
+    let state = app.clone();
+    window.motion_hook(move |x, y, _| state.borrow_mut().mouse_move(x, y), &());
 
-    window.key_hook(
-        move |keycode, _| {
+    let state = app.clone();
+    window.key_press_hook(
+        move |keycode, modifiers, _| {
             // you can also check keycodes using the `xev` command
             println!("{}", keycode);
 
-            // `q` or `esc`
-            if keycode == 113 || keycode == 65307 {
-                mlx.destroy_image(&image);
-                mlx.destroy_window(&window);
-                mlx.destroy();
-                process::exit(0);
-            }
+            state.borrow_mut().key_press(keycode, modifiers);
         },
         &(),
     );
+    // the hooks borrow the app mutably
+    drop(app_ref);
 
-    // this will loop forever
-    mlx.event_loop();
+    let state = app.clone();
+    if let Err(e) = mlx.event_loop_until(move || state.borrow().quitting()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,67 @@
+//! Newton fractal of `z³ - 1`, the basins of its three roots under Newton's method.
+//!
+//! Each point is a starting guess, refined by `z → z - (z³ - 1) / 3z²` until it lands on one of the roots,
+//! the cube roots of unity. The basins meet along a fractal boundary, where the guesses take the longest.
+
+use crate::render::RenderConfig;
+use crate::viewport::Viewport;
+use mlx::colors;
+use num_complex::Complex;
+
+/// A guess this close to a root has converged to it.
+const EPSILON: f64 = 1e-6;
+/// Colors of the basins of the roots, in the order of [roots].
+const ROOT_COLORS: [u32; 3] = [colors::RED, colors::LIME, colors::BLUE];
+/// Brightness kept by each iteration, the slower guesses getting darker.
+const FADE: f64 = 0.92;
+
+/// The cube roots of unity, `1` then `e^(±2iπ/3)`.
+fn roots() -> [Complex<f64>; 3] {
+    let (re, im) = (-0.5, 3f64.sqrt() / 2.0);
+    [
+        Complex::new(1.0, 0.0),
+        Complex::new(re, im),
+        Complex::new(re, -im),
+    ]
+}
+
+/// Index in [roots] of the root reached from `z`, and the number of iterations it took,
+/// `None` when the guess has not converged after `iterations`.
+pub fn converge(mut z: Complex<f64>, iterations: u32) -> Option<(usize, u32)> {
+    let roots = roots();
+    for n in 0..iterations {
+        if let Some(root) = roots
+            .iter()
+            .position(|&root| (z - root).norm_sqr() < EPSILON * EPSILON)
+        {
+            return Some((root, n));
+        }
+        let z2 = z * z;
+        // the origin is sent to infinity, then to NaN, which never converges
+        z -= (z2 * z - 1.0) / (3.0 * z2);
+    }
+    None
+}
+
+/// Color of the point `c` of the view, the color of the root it converges to,
+/// dimmed by [FADE] for each iteration it took, or the inside color when it does not converge.
+pub fn color(c: Complex<f64>, config: &RenderConfig) -> u32 {
+    let Some((root, n)) = converge(c, config.max_iterations) else {
+        return config.inside_color;
+    };
+
+    let brightness = FADE.powi(n as i32);
+    let channel = |shift: u32| {
+        let value = (ROOT_COLORS[root] >> shift & 0xff) as f64 * brightness;
+        (value as u32) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
+
+/// View of the three roots, around the origin.
+pub fn default_viewport(width: i32, height: i32) -> Viewport {
+    Viewport {
+        scale: 3.0,
+        ..Viewport::new(width, height)
+    }
+}
@@ -0,0 +1,240 @@
+use crate::fractal::Mode;
+use crate::render::Tile;
+use crate::viewport::Viewport;
+use mlx::widgets::{Rect, CHAR_WIDTH};
+use mlx::{colors, Mlx, MlxImage, MlxWindow, LINE_HEIGHT};
+use num_complex::Complex;
+
+const AXIS_COLOR: u32 = colors::WHITE;
+const MAJOR_COLOR: u32 = colors::GRAY;
+const MINOR_COLOR: u32 = 0x404040;
+const LABEL_COLOR: u32 = colors::WHITE;
+const HUD_COLOR: u32 = colors::WHITE;
+const HUD_OUTLINE_COLOR: u32 = colors::BLACK;
+const SELECTION_COLOR: u32 = colors::WHITE;
+const CROSSHAIR_COLOR: u32 = colors::SILVER;
+const PREVIEW_COLOR: u32 = colors::YELLOW;
+const ORBIT_COLOR: u32 = colors::AQUA;
+const DIVIDER_COLOR: u32 = colors::WHITE;
+const BUTTON_HEIGHT: i32 = 20;
+const BUTTON_PADDING: i32 = 8;
+const BUTTON_MARGIN: i32 = 10;
+
+/// Number of major gridlines wanted across the shortest side of the view.
+const MAJOR_LINES: f64 = 4.0;
+
+/// Spacing of the grid, in complex units.
+struct Grid {
+    minor: f64,
+    /// number of minor steps between two major lines
+    per_major: i64,
+}
+
+impl Grid {
+    /// Picks a 1, 2 or 5 times a power of ten spacing so the number of lines stays the same at any zoom level.
+    fn new(viewport: &Viewport) -> Self {
+        let span = viewport.span_re().min(viewport.span_im());
+        let raw = span / MAJOR_LINES;
+        let magnitude = 10f64.powf(raw.log10().floor());
+        let (major, per_major) = match raw / magnitude {
+            m if m < 2.0 => (magnitude, 5),
+            m if m < 5.0 => (2.0 * magnitude, 4),
+            _ => (5.0 * magnitude, 5),
+        };
+        Self {
+            minor: major / per_major as f64,
+            per_major,
+        }
+    }
+
+    fn major(&self) -> f64 {
+        self.minor * self.per_major as f64
+    }
+
+    /// Indexes of the minor lines between `min` and `max`.
+    fn lines(&self, min: f64, max: f64) -> std::ops::RangeInclusive<i64> {
+        (min / self.minor).ceil() as i64..=(max / self.minor).floor() as i64
+    }
+
+    fn color(&self, line: i64) -> u32 {
+        if line == 0 {
+            AXIS_COLOR
+        } else if line % self.per_major == 0 {
+            MAJOR_COLOR
+        } else {
+            MINOR_COLOR
+        }
+    }
+
+    /// Enough decimals to tell two major lines apart.
+    fn label(&self, value: f64) -> String {
+        let decimals = (-self.major().log10().floor()).max(0.0) as usize;
+        format!("{:.*}", decimals, value)
+    }
+}
+
+/// Complex numbers at the top left and bottom right corners of the view.
+fn bounds(viewport: &Viewport) -> (Complex<f64>, Complex<f64>) {
+    (
+        viewport.pixel_to_complex(0.0, 0.0),
+        viewport.pixel_to_complex(viewport.width as f64, viewport.height as f64),
+    )
+}
+
+/// Draws the complex plane axes and the gridlines into the image.
+pub fn draw_grid(image: &MlxImage, viewport: &Viewport) {
+    let grid = Grid::new(viewport);
+    let (min, max) = bounds(viewport);
+
+    for line in grid.lines(min.re, max.re) {
+        let (x, _) = viewport.complex_to_pixel(Complex::new(line as f64 * grid.minor, 0.0));
+        image.draw_line(x as i32, 0, x as i32, image.height - 1, grid.color(line));
+    }
+    for line in grid.lines(min.im, max.im) {
+        let (_, y) = viewport.complex_to_pixel(Complex::new(0.0, line as f64 * grid.minor));
+        image.draw_line(0, y as i32, image.width - 1, y as i32, grid.color(line));
+    }
+}
+
+/// Writes the coordinates of the major gridlines on the window.
+///
+/// Must be called after the image has been put to the window, otherwise it would cover the labels.
+pub fn draw_grid_labels(mlx: &Mlx, window: &MlxWindow, viewport: &Viewport) {
+    let grid = Grid::new(viewport);
+    let (min, max) = bounds(viewport);
+
+    for line in grid
+        .lines(min.re, max.re)
+        .filter(|line| line % grid.per_major == 0)
+    {
+        let re = line as f64 * grid.minor;
+        let (x, _) = viewport.complex_to_pixel(Complex::new(re, 0.0));
+        let label = grid.label(re);
+        let _ = mlx.string_put(
+            window,
+            x as i32 + 2,
+            viewport.height - 4,
+            LABEL_COLOR,
+            &label,
+        );
+    }
+    for line in grid
+        .lines(min.im, max.im)
+        .filter(|line| line % grid.per_major == 0)
+    {
+        let im = line as f64 * grid.minor;
+        let (_, y) = viewport.complex_to_pixel(Complex::new(0.0, im));
+        let label = format!("{}i", grid.label(im));
+        let _ = mlx.string_put(window, 2, y as i32 - 2, LABEL_COLOR, &label);
+    }
+}
+
+/// Draws the outline of the rectangle between the corners `from` and `to` into the image.
+pub fn draw_selection(image: &MlxImage, from: (i32, i32), to: (i32, i32)) {
+    let ((x0, y0), (x1, y1)) = (from, to);
+    let (width, height) = ((x1 - x0).abs() + 1, (y1 - y0).abs() + 1);
+    image.draw_rect(x0.min(x1), y0.min(y1), width, height, SELECTION_COLOR);
+}
+
+/// Draws the outline of the preview region, the only part of the frame rendered in preview mode.
+pub fn draw_preview(image: &MlxImage, region: Tile) {
+    image.draw_rect(
+        region.x,
+        region.y,
+        region.width,
+        region.height,
+        PREVIEW_COLOR,
+    );
+}
+
+/// Draws the line between the two sides of the split screen, at the column `x`.
+pub fn draw_divider(image: &MlxImage, x: i32) {
+    image.draw_line(x, 0, x, image.height - 1, DIVIDER_COLOR);
+}
+
+/// Draws lines across the image crossing at the pixel `(x, y)`.
+pub fn draw_crosshair(image: &MlxImage, x: i32, y: i32) {
+    image.draw_line(0, y, image.width - 1, y, CROSSHAIR_COLOR);
+    image.draw_line(x, 0, x, image.height - 1, CROSSHAIR_COLOR);
+}
+
+/// Draws the orbit of a point as lines joining its successive values.
+pub fn draw_orbit(image: &MlxImage, viewport: &Viewport, orbit: &[Complex<f64>]) {
+    let bounds = (image.width as f64, image.height as f64);
+    for pair in orbit.windows(2) {
+        let (from, to) = (
+            viewport.complex_to_pixel(pair[0]),
+            viewport.complex_to_pixel(pair[1]),
+        );
+        // escaping values go far out of the image, the lines would take forever to draw
+        if let Some(((x0, y0), (x1, y1))) = clip_segment(from, to, bounds) {
+            image.draw_line(x0 as i32, y0 as i32, x1 as i32, y1 as i32, ORBIT_COLOR);
+        }
+    }
+}
+
+/// Part of the segment between `from` and `to` inside of the `(0, 0)` to `(width, height)` rectangle,
+/// with the Liang-Barsky algorithm.
+fn clip_segment(
+    from: (f64, f64),
+    to: (f64, f64),
+    (width, height): (f64, f64),
+) -> Option<((f64, f64), (f64, f64))> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (mut start, mut end) = (0.0f64, 1.0f64);
+    // each side as the component of the direction going out through it, and the room left before it
+    let sides = [
+        (-dx, from.0),
+        (dx, width - 1.0 - from.0),
+        (-dy, from.1),
+        (dy, height - 1.0 - from.1),
+    ];
+    for (out, room) in sides {
+        if out == 0.0 {
+            if room < 0.0 {
+                return None;
+            }
+        } else if out < 0.0 {
+            start = start.max(room / out);
+        } else {
+            end = end.min(room / out);
+        }
+    }
+    let point = |t: f64| (from.0 + t * dx, from.1 + t * dy);
+    (start <= end).then(|| (point(start), point(end)))
+}
+
+/// Complex number under the pixel `(x, y)`, with enough digits to tell neighbouring pixels apart.
+pub fn coordinate(viewport: &Viewport, x: i32, y: i32) -> String {
+    let z = viewport.pixel_to_complex(x as f64, y as f64);
+    let pixel_size = viewport.span_im() / viewport.height as f64;
+    let digits = (-pixel_size.log10()).ceil().max(0.0) as usize;
+    format!("{:.*} {:+.*}i", digits, z.re, digits, z.im)
+}
+
+/// Writes the lines of the HUD in the top left corner of the window, outlined to stay readable over the fractal.
+pub fn draw_hud(mlx: &Mlx, window: &MlxWindow, lines: &[String]) {
+    // the lines are built by the app and have no NUL byte
+    let _ = mlx.string_put_outlined(
+        window,
+        10,
+        LINE_HEIGHT,
+        HUD_COLOR,
+        HUD_OUTLINE_COLOR,
+        &lines.join("\n"),
+    );
+}
+
+/// Buttons of the fractal switcher, in a row at the bottom left of a window `height` pixels high.
+pub fn switcher_buttons(height: i32) -> Vec<(Rect, Mode)> {
+    let y = height - BUTTON_MARGIN - BUTTON_HEIGHT;
+    let mut x = BUTTON_MARGIN;
+    Mode::all()
+        .map(|mode| {
+            let width = CHAR_WIDTH * mode.name().len() as i32 + 2 * BUTTON_PADDING;
+            let rect = Rect::new(x, y, width, BUTTON_HEIGHT);
+            x += width + BUTTON_MARGIN / 2;
+            (rect, mode)
+        })
+        .collect()
+}
@@ -0,0 +1,187 @@
+//! Colors given to the iteration counts.
+
+use crate::rng::Rng;
+use mlx::Color;
+use std::f64::consts::TAU;
+use std::fmt;
+use std::str::FromStr;
+
+/// Largest seed of the random palettes, so their names stay short enough to note down.
+const MAX_SEED: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Palette {
+    Default,
+    /// one of the [GRADIENTS]
+    Gradient(&'static Gradient),
+    /// generated from a seed, see [CosinePalette]
+    Random(CosinePalette),
+}
+
+/// The gradient presets, in the order they are cycled through.
+pub const GRADIENTS: [Gradient; 4] = [
+    Gradient {
+        name: "fire",
+        stops: &[
+            Color::rgb(0, 0, 0),
+            Color::rgb(128, 0, 0),
+            Color::rgb(255, 64, 0),
+            Color::rgb(255, 192, 0),
+            Color::rgb(255, 255, 224),
+        ],
+    },
+    Gradient {
+        name: "ocean",
+        stops: &[
+            Color::rgb(0, 0, 32),
+            Color::rgb(0, 32, 96),
+            Color::rgb(0, 128, 160),
+            Color::rgb(64, 224, 208),
+            Color::rgb(240, 255, 255),
+        ],
+    },
+    Gradient {
+        name: "grayscale",
+        stops: &[Color::rgb(0, 0, 0), Color::rgb(255, 255, 255)],
+    },
+    Gradient {
+        name: "rainbow",
+        stops: &[
+            Color::rgb(255, 0, 0),
+            Color::rgb(255, 255, 0),
+            Color::rgb(0, 255, 0),
+            Color::rgb(0, 255, 255),
+            Color::rgb(0, 0, 255),
+            Color::rgb(255, 0, 255),
+        ],
+    },
+];
+
+impl Palette {
+    /// Every palette but the random ones, in the order they are cycled through.
+    fn presets() -> impl Iterator<Item = Palette> {
+        [Palette::Default]
+            .into_iter()
+            .chain(GRADIENTS.iter().map(Palette::Gradient))
+    }
+
+    /// The preset after this palette, `step` can be negative to go back.
+    ///
+    /// A random palette goes to the first or last preset.
+    pub fn cycle(self, step: isize) -> Self {
+        let presets: Vec<Palette> = Self::presets().collect();
+        let next = match presets.iter().position(|&palette| palette == self) {
+            Some(current) => current as isize + step,
+            None if step > 0 => 0,
+            None => -1,
+        };
+        presets[next.rem_euclid(presets.len() as isize) as usize]
+    }
+
+    /// Palette generated from `seed`, the same seed always giving the same palette.
+    pub fn random(seed: u64) -> Self {
+        Palette::Random(CosinePalette::new(seed % MAX_SEED))
+    }
+
+    /// Color at `t` along the gradient, the fraction of the iterations done in `[0, 1]`.
+    pub fn color_at(&self, t: f64) -> u32 {
+        let (r, g, b) = match self {
+            // the points escaping right away are black,
+            // the ones escaping later and later, nearer to the set, go through purple and blue to white
+            Palette::Default => (t, t * t, t.sqrt()),
+            Palette::Gradient(gradient) => return gradient.sample(t).as_u32(),
+            Palette::Random(palette) => palette.sample(t),
+        };
+        let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::rgb(channel(r), channel(g), channel(b)).as_u32()
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Palette::Default => write!(f, "default"),
+            Palette::Gradient(gradient) => write!(f, "{}", gradient.name),
+            Palette::Random(palette) => write!(f, "random-{}", palette.seed),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("random-") {
+            Some(seed) => seed.parse().map(Palette::random).map_err(|_| ()),
+            None => Self::presets()
+                .find(|palette| palette.to_string() == s)
+                .ok_or(()),
+        }
+    }
+}
+
+/// Colors interpolated linearly between stops spread evenly from 0 to 1.
+#[derive(Debug, PartialEq)]
+pub struct Gradient {
+    name: &'static str,
+    stops: &'static [Color],
+}
+
+impl Gradient {
+    /// Color at `t` in `[0, 1]` along the gradient, `t` is clamped.
+    pub fn sample(&self, t: f64) -> Color {
+        let last = self.stops.len() - 1;
+        let position = t.clamp(0.0, 1.0) * last as f64;
+        let i = (position as usize).min(last.saturating_sub(1));
+        let (from, to) = (
+            self.stops[i].as_u32(),
+            self.stops[(i + 1).min(last)].as_u32(),
+        );
+        let fraction = position - i as f64;
+        let channel = |shift: u32| {
+            let (a, b) = ((from >> shift & 0xff) as f64, (to >> shift & 0xff) as f64);
+            (a + (b - a) * fraction).round() as u8
+        };
+        Color::rgb(channel(16), channel(8), channel(0))
+    }
+}
+
+/// Gradient where each channel is a cosine wave `a + b cos(2π (c t + d))`, after Inigo Quilez.
+///
+/// The coefficients are picked at random for each channel, `a ± b` staying within `[0, 1]`
+/// so the colors do not clip, which keeps the gradients smooth and pleasant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CosinePalette {
+    seed: u64,
+    a: [f64; 3],
+    b: [f64; 3],
+    c: [f64; 3],
+    d: [f64; 3],
+}
+
+impl CosinePalette {
+    fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut palette = Self {
+            seed,
+            a: [0.0; 3],
+            b: [0.0; 3],
+            c: [0.0; 3],
+            d: [0.0; 3],
+        };
+        for k in 0..3 {
+            palette.a[k] = 0.3 + 0.4 * rng.next_f64();
+            palette.b[k] = (0.2 + 0.3 * rng.next_f64())
+                .min(palette.a[k])
+                .min(1.0 - palette.a[k]);
+            palette.c[k] = 0.5 + 1.5 * rng.next_f64();
+            palette.d[k] = rng.next_f64();
+        }
+        palette
+    }
+
+    fn sample(&self, t: f64) -> (f64, f64, f64) {
+        let channel = |k: usize| self.a[k] + self.b[k] * (TAU * (self.c[k] * t + self.d[k])).cos();
+        (channel(0), channel(1), channel(2))
+    }
+}
@@ -0,0 +1,98 @@
+//! Deep zoom into the Mandelbrot set with perturbation theory.
+//!
+//! Past a zoom of about 10^13, neighbouring pixels map to complex numbers `f64` cannot tell apart.
+//! Instead, a single reference orbit `Z` is computed precisely at the center of the view,
+//! and each pixel only tracks the small difference `δ` between its orbit and the reference one:
+//!
+//! ```text
+//! z = Z + δ
+//! δ(n+1) = 2 Z(n) δ(n) + δ(n)² + δc
+//! ```
+//!
+//! where `δc` is the offset of the pixel from the center. These differences stay within what `f64` handles.
+//! When a pixel outlives the reference orbit or gets close to it (a "glitch", where `δ` loses its precision),
+//! the pixel falls back to the direct `f64` iteration.
+
+use crate::double_double::{self, DoubleDouble};
+use crate::fractal::{Escape, EscapeTime};
+use crate::render::RenderConfig;
+use crate::viewport::Viewport;
+use num_complex::Complex;
+
+/// A pixel glitches when `|z|² < GLITCH_TOLERANCE * |Z|²`.
+const GLITCH_TOLERANCE: f64 = 1e-6;
+
+/// Iterates the pixel `(x, y)`, see [EscapeTime::escape].
+///
+/// `orbit` is the [reference orbit](reference_orbit) of the center of the view.
+/// Only the count and the last `z` are computed, unless the pixel falls back to the direct iteration.
+pub fn escape(
+    fractal: &dyn EscapeTime,
+    orbit: &[Complex<f64>],
+    viewport: &Viewport,
+    config: &RenderConfig,
+    (x, y): (f64, f64),
+) -> Escape {
+    let dc = viewport.pixel_offset(x, y);
+    match delta_escape(orbit, dc, config) {
+        Some((count, z)) => Escape {
+            count,
+            z,
+            dz: Complex::new(0.0, 0.0),
+            trap: f64::INFINITY,
+        },
+        None => fractal.escape(
+            double_double::round(double_double::offset(viewport.center, dc)),
+            config,
+        ),
+    }
+}
+
+/// Iterations left and last `z` when the pixel `dc` away from the reference escapes, `None` if it glitched.
+fn delta_escape(
+    orbit: &[Complex<f64>],
+    dc: Complex<f64>,
+    config: &RenderConfig,
+) -> Option<(u32, Complex<f64>)> {
+    let radius_sqr = config.escape_radius * config.escape_radius;
+    let mut dz = Complex::new(0.0, 0.0);
+    let mut i = config.max_iterations;
+    let mut n = 0;
+
+    while i > 0 {
+        let z = orbit[n] + dz;
+        if z.norm_sqr() >= radius_sqr {
+            return Some((i, z));
+        }
+        if z.norm_sqr() < GLITCH_TOLERANCE * orbit[n].norm_sqr() || n + 1 >= orbit.len() {
+            return None;
+        }
+        dz = 2.0 * orbit[n] * dz + dz * dz + dc;
+        n += 1;
+        i -= 1;
+    }
+    Some((0, orbit[n] + dz))
+}
+
+/// Orbit of `c` under `z² + c`, computed in double-double precision and rounded to `f64`.
+pub fn reference_orbit(c: Complex<DoubleDouble>, config: &RenderConfig) -> Vec<Complex<f64>> {
+    let radius_sqr = config.escape_radius * config.escape_radius;
+    let (cr, ci) = (c.re, c.im);
+    let (mut zr, mut zi) = (DoubleDouble::new(0.0), DoubleDouble::new(0.0));
+    let mut orbit = vec![Complex::new(0.0, 0.0)];
+
+    while orbit.len() <= config.max_iterations as usize {
+        let (re, im) = (
+            zr.mul(zr).sub(zi.mul(zi)).add(cr),
+            zr.mul(zi).double().add(ci),
+        );
+        zr = re;
+        zi = im;
+        let z = Complex::new(zr.hi, zi.hi);
+        orbit.push(z);
+        if z.norm_sqr() >= radius_sqr {
+            break;
+        }
+    }
+    orbit
+}
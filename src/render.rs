@@ -0,0 +1,1096 @@
+use crate::fractal::{Escape, EscapeTime, Mode, Tracking, FRACTALS};
+use crate::koch;
+use crate::lyapunov::{self, Sequence};
+use crate::newton;
+use crate::palette::Palette;
+use crate::perturbation;
+use crate::sierpinski;
+use crate::viewport::Viewport;
+use mlx::{colors, Color};
+use num_complex::Complex;
+use std::f64::consts::TAU;
+use std::fmt;
+use std::hint::black_box;
+use std::str::FromStr;
+#[cfg(feature = "threads")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "threads")]
+use std::sync::Mutex;
+#[cfg(feature = "threads")]
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const MAX_ITERATIONS: u32 = 110;
+pub const ESCAPE_RADIUS: f64 = 2.0;
+pub const POINTS: u32 = 200_000;
+pub const DEPTH: u32 = 4;
+pub const MIN_POINTS: u32 = 1_000;
+pub const MAX_POINTS: u32 = 10_000_000;
+/// the segments are 4 times more numerous at each level
+pub const MAX_DEPTH: u32 = 8;
+pub const MAX_AUTO_ITERATIONS: u32 = 2000;
+pub const JULIA_CONSTANT: Complex<f64> = Complex::new(-0.9, 0.27015);
+
+/// Colors of the bailout view, see [RenderConfig::bailout_view].
+const ESCAPED_COLOR: u32 = colors::NAVY;
+const CAPPED_COLOR: u32 = colors::WHITE;
+const NOT_FINITE_COLOR: u32 = colors::RED;
+
+/// The potential coloring takes this root of the potential, see [ColorMode::Potential].
+const POTENTIAL_FALLOFF: f64 = 8.0;
+/// Distance to the set, in pixels, at the end of the palette for [ColorMode::Distance].
+const DISTANCE_FALLOFF: f64 = 64.0;
+
+/// Iterations added by the auto scaling for each tenfold zoom.
+const ITERATIONS_PER_DECADE: f64 = 50.0;
+
+/// Floating point type used to iterate the escape-time fractals.
+///
+/// Details finer than the precision of the type get pixelated:
+/// `f32` holds up to a zoom of about x10^4 of the default view, `f64` up to about x10^13.
+/// The [perturbation](crate::perturbation) renderer always uses `f64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    Single,
+    Double,
+}
+
+impl Precision {
+    pub fn toggle(self) -> Self {
+        match self {
+            Precision::Single => Precision::Double,
+            Precision::Double => Precision::Single,
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Precision::Single => write!(f, "f32"),
+            Precision::Double => write!(f, "f64"),
+        }
+    }
+}
+
+impl FromStr for Precision {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f32" => Ok(Precision::Single),
+            "f64" => Ok(Precision::Double),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How iteration counts are spread over the palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Linear,
+    /// gives more colors to the points escaping late, near the boundary of the set
+    Logarithmic,
+    /// hue of the angle of the last `z`, turning around the set
+    Angle,
+    /// continuous potential of the point, shading the equipotential lines smoothly
+    ///
+    /// After `n` iterations, the potential (Green's function) is `G = ln|z| / 2^n`:
+    /// 0 on the set, it grows continuously away from it, unlike the iteration count.
+    /// The palette is sampled at `1 - G^(1/8)`, the root spreading the tiny potentials near the set.
+    Potential,
+    /// continuous iteration count, without the bands of the whole counts
+    ///
+    /// A point escaping after `n` iterations with a last value `z` has escaped after `ν = n + 1 - log2(ln|z|)`
+    /// iterations: `|z|` grows about as `|z|²` at each iteration past the escape radius,
+    /// so how far `z` went past it tells how early in the last iteration it escaped.
+    Smooth,
+    /// rank of the iteration count among the pixels of the frame, spreading them evenly over the palette
+    ///
+    /// The palette is sampled at the fraction of the escaped samples of the frame escaping no later than the point.
+    /// The counts of all the samples are kept while the frame is iterated, and the pixels are only colored
+    /// from their rank once it is complete, see [TiledRender]. Until then they are colored as [ColorMode::Linear].
+    Histogram,
+    /// estimated distance to the set, in pixels, outlining its thinnest filaments
+    ///
+    /// Past the escape radius `G = ln|z| / 2^n` as for [ColorMode::Potential], and the distance is about `G / |G'|`,
+    /// which is `|z| ln|z| / |z'|` with `z'` the derivative of `z` with respect to the point.
+    /// The palette is sampled at `1 - (d / DISTANCE_FALLOFF)^(1/2)`, so the points near the set look like late escapes.
+    Distance,
+    /// closest the orbit came to the origin, an orbit trap drawing rings around the escaping points
+    Trap,
+}
+
+impl ColorMode {
+    const ALL: [ColorMode; 8] = [
+        ColorMode::Linear,
+        ColorMode::Logarithmic,
+        ColorMode::Angle,
+        ColorMode::Potential,
+        ColorMode::Smooth,
+        ColorMode::Histogram,
+        ColorMode::Distance,
+        ColorMode::Trap,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMode::Linear => "linear",
+            ColorMode::Logarithmic => "logarithmic",
+            ColorMode::Angle => "angle",
+            ColorMode::Potential => "potential",
+            ColorMode::Smooth => "smooth",
+            ColorMode::Histogram => "histogram",
+            ColorMode::Distance => "distance",
+            ColorMode::Trap => "trap",
+        }
+    }
+
+    /// The next mode, going back to the first one after the last.
+    pub fn cycle(self) -> Self {
+        let current = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(current + 1) % Self::ALL.len()]
+    }
+
+    /// What the mode needs computed along the iterations, on top of the count.
+    pub fn tracking(self) -> Tracking {
+        Tracking {
+            derivative: self == ColorMode::Distance,
+            trap: self == ColorMode::Trap,
+        }
+    }
+
+    /// Whether the complex conjugate of a point gets the same color, so mirrored rows can be copied.
+    ///
+    /// The angle of the conjugate `z̄` is the opposite one, which gives it another hue.
+    pub fn mirror_safe(self) -> bool {
+        self != ColorMode::Angle
+    }
+
+    /// Maps `i` iterations left out of `max` to the iterations left given to the palette.
+    fn map(self, i: u32, max: u32) -> u32 {
+        match self {
+            // the other modes do not use the mapped count, the histogram and the distance fall back to linear
+            // without a whole frame or a renderer, see [Renderer::color]
+            ColorMode::Linear
+            | ColorMode::Angle
+            | ColorMode::Potential
+            | ColorMode::Smooth
+            | ColorMode::Histogram
+            | ColorMode::Distance
+            | ColorMode::Trap => i,
+            ColorMode::Logarithmic => {
+                let done = max.saturating_sub(i) as f64;
+                let spread = (done + 1.0).ln() / (max as f64 + 1.0).ln() * max as f64;
+                max.saturating_sub(spread as u32).max(1)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or(())
+    }
+}
+
+/// Settings of the renderers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderConfig {
+    pub max_iterations: u32,
+    /// raise the iterations as the view is zoomed in, see [RenderConfig::iterations_at]
+    pub auto_iterations: bool,
+    /// most iterations given by the auto scaling, so the frames do not take forever
+    pub max_auto_iterations: u32,
+    /// a point escapes once its distance to the origin goes past this radius
+    pub escape_radius: f64,
+    /// `c` of the Julia set, which iterates `z² + c` from each point
+    pub julia_constant: Complex<f64>,
+    /// number of points plotted by the chaos game
+    pub points: u32,
+    /// number of subdivisions of the line fractals
+    pub depth: u32,
+    /// order of the rates of the Lyapunov fractal
+    pub sequence: Sequence,
+    /// use the perturbation renderer for the fractals supporting it
+    pub perturbation: bool,
+    /// color of the points that never escape
+    pub inside_color: u32,
+    pub precision: Precision,
+    pub coloring: ColorMode,
+    pub palette: Palette,
+    /// rotates the palette by this fraction of its length, see [RenderConfig::palette_color_at]
+    pub palette_offset: f64,
+    /// stretches the palette this many times over the iterations, repeating it past the end
+    pub palette_contrast: f64,
+    /// colors the points by how their iteration ended instead: escaped, stopped by the iteration cap,
+    /// or overflowed to an infinite or NaN value, which shows where the precision breaks down
+    pub bailout_view: bool,
+    /// each pixel averages `supersampling * supersampling` samples spread over it, 1 to take a single one
+    pub supersampling: u32,
+    /// threads rendering the escape-time fractals with the `threads` feature, 0 for one per core
+    pub threads: usize,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: MAX_ITERATIONS,
+            auto_iterations: false,
+            max_auto_iterations: MAX_AUTO_ITERATIONS,
+            escape_radius: ESCAPE_RADIUS,
+            julia_constant: JULIA_CONSTANT,
+            points: POINTS,
+            depth: DEPTH,
+            sequence: Sequence::default(),
+            perturbation: false,
+            inside_color: 0x000000,
+            precision: Precision::Double,
+            coloring: ColorMode::Linear,
+            palette: Palette::Default,
+            palette_offset: 0.0,
+            palette_contrast: 1.0,
+            bailout_view: false,
+            supersampling: 1,
+            threads: 0,
+        }
+    }
+}
+
+impl RenderConfig {
+    /// Iterations used at `zoom` times the default view.
+    ///
+    /// This is `max_iterations`, plus [ITERATIONS_PER_DECADE] for each tenfold zoom with the auto scaling,
+    /// up to `max_auto_iterations`.
+    pub fn iterations_at(&self, zoom: f64) -> u32 {
+        if !self.auto_iterations {
+            return self.max_iterations;
+        }
+        let iterations = self.max_iterations as f64 + ITERATIONS_PER_DECADE * zoom.log10().max(0.0);
+        iterations.min(self.max_auto_iterations.max(self.max_iterations) as f64) as u32
+    }
+
+    /// Color of an iterated point, see [EscapeTime::escape].
+    pub fn color(&self, escape: &Escape) -> u32 {
+        if self.bailout_view {
+            return match escape.count {
+                _ if !escape.z.is_finite() => NOT_FINITE_COLOR,
+                0 => CAPPED_COLOR,
+                _ => ESCAPED_COLOR,
+            };
+        }
+        if escape.count == 0 {
+            return self.inside_color;
+        }
+        match self.coloring {
+            ColorMode::Angle => hue(escape.z.arg() / TAU + 0.5),
+            ColorMode::Potential => {
+                let done = self.max_iterations.saturating_sub(escape.count);
+                let potential = escape.z.norm().ln() / 2f64.powi(done as i32);
+                self.palette_color_at(1.0 - potential.max(0.0).powf(1.0 / POTENTIAL_FALLOFF))
+            }
+            ColorMode::Trap => {
+                self.palette_color_at(1.0 - (escape.trap / self.escape_radius).clamp(0.0, 1.0))
+            }
+            ColorMode::Smooth => {
+                let done = self.max_iterations.saturating_sub(escape.count) as f64;
+                let smooth = done + 1.0 - escape.z.norm().ln().log2();
+                // an overflowed `z` gives no fraction, it is taken as escaping right away
+                let smooth = if smooth.is_nan() { 0.0 } else { smooth };
+                self.palette_color_at((smooth / self.max_iterations.max(1) as f64).clamp(0.0, 1.0))
+            }
+            coloring => {
+                let max = self.max_iterations;
+                self.palette_color(coloring.map(escape.count, max), max)
+            }
+        }
+    }
+
+    /// Color of `i` iterations left out of `max`.
+    pub fn palette_color(&self, i: u32, max: u32) -> u32 {
+        self.palette_color_at(1.0 - (i as f64 / max.max(1) as f64).min(1.0))
+    }
+
+    /// Color at `t` along the palette, the fraction of the iterations done in `[0, 1]`.
+    ///
+    /// `t` is scaled by the contrast and shifted by the offset, the values past the ends of the palette
+    /// wrapping around to the other end.
+    pub fn palette_color_at(&self, t: f64) -> u32 {
+        let t = t * self.palette_contrast + self.palette_offset;
+        // the end of the palette is kept when nothing wraps
+        let t = if (0.0..=1.0).contains(&t) {
+            t
+        } else {
+            t.rem_euclid(1.0)
+        };
+        self.palette.color_at(t)
+    }
+}
+
+/// Fully saturated color at `turns` around the hue wheel, red at 0 and 1.
+pub fn hue(turns: f64) -> u32 {
+    Color::hsv((turns.rem_euclid(1.0) * 360.0) as f32, 1.0, 1.0).as_u32()
+}
+
+/// Time between two reports of the progress of [render_fractal].
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Renders the fractal seen through `viewport` into `frame`, one color per pixel, tile after tile.
+///
+/// `progress` is called with the fraction of the frame done as the render goes, about every [PROGRESS_INTERVAL].
+pub fn render_fractal(
+    mode: Mode,
+    viewport: &Viewport,
+    config: &RenderConfig,
+    frame: &mut [u32],
+    mut progress: Option<&mut dyn FnMut(f32)>,
+) {
+    if mode == Mode::Sierpinski {
+        return sierpinski::chaos_game(viewport, config, frame, progress);
+    }
+    let mut job = TiledRender::new(Renderer::new(mode, viewport, config), None, None, false);
+    loop {
+        let complete = job.step(frame, PROGRESS_INTERVAL, &|| false);
+        report(&mut progress, job.next, job.tiles.len());
+        if complete {
+            break;
+        }
+    }
+}
+
+/// Rectangle of pixels of a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Tile {
+    /// Pixels in both tiles, empty when they do not overlap.
+    pub fn intersection(&self, other: &Tile) -> Tile {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Tile {
+            x,
+            y,
+            width: (right - x).max(0),
+            height: (bottom - y).max(0),
+        }
+    }
+}
+
+/// Sizes of the tiles picked by [Renderer::tile_size].
+pub const MIN_TILE_SIZE: i32 = 16;
+pub const MAX_TILE_SIZE: i32 = 256;
+
+/// Time between two checks for an interruption while the threads render, see [TiledRender::step].
+#[cfg(feature = "threads")]
+const INTERRUPT_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Time a tile should take to render.
+///
+/// The renders are spread over the loop iterations a few tiles at a time, which are at most 25ms long
+/// to keep up with the input. 2ms tiles overshoot this budget by little,
+/// and still make the bookkeeping of each tile negligible.
+const TILE_TIME: Duration = Duration::from_millis(2);
+
+/// Number of pixels timed along each side of the frame to estimate the cost of a pixel.
+const COST_SAMPLES: i32 = 16;
+
+/// Side of the blocks of pixels of the coarse pass of the progressive renders, see [TiledRender::new].
+const COARSE_BLOCK: i32 = 8;
+
+/// Computes the pixels of a frame, in any number of parts.
+pub struct Renderer {
+    mode: Mode,
+    viewport: Viewport,
+    config: RenderConfig,
+    /// reference orbit of the perturbation renderer
+    orbit: Option<Vec<Complex<f64>>>,
+    /// the rows below the real axis are copies of the ones above
+    mirrored: bool,
+}
+
+impl Renderer {
+    pub fn new(mode: Mode, viewport: &Viewport, config: &RenderConfig) -> Self {
+        let mut config = *config;
+        config.max_iterations = config.iterations_at(mode.zoom(viewport));
+        let (orbit, mirrored) = match mode {
+            // the perturbation renderer only computes the count and the last `z`
+            Mode::EscapeTime(fractal)
+                if config.perturbation
+                    && FRACTALS[fractal].perturbation()
+                    && config.coloring.tracking() == Tracking::default() =>
+            {
+                // glitched pixels are too deep for `f32`
+                config.precision = Precision::Double;
+                (
+                    Some(perturbation::reference_orbit(viewport.center, &config)),
+                    false,
+                )
+            }
+            Mode::EscapeTime(fractal) => {
+                let symmetric = FRACTALS[fractal].symmetric();
+                let mirrored = symmetric && config.coloring.mirror_safe();
+                (None, mirrored && viewport.centered_on_real_axis())
+            }
+            Mode::Sierpinski | Mode::Koch | Mode::Lyapunov | Mode::Newton => (None, false),
+        };
+        Self {
+            mode,
+            viewport: *viewport,
+            config,
+            orbit,
+            mirrored,
+        }
+    }
+
+    /// Number of samples whose iterations left are kept for each pixel while rendering,
+    /// 0 unless the pixels are colored by [ColorMode::Histogram] once the frame is complete.
+    pub fn counted_samples(&self) -> usize {
+        let counted = matches!(self.mode, Mode::EscapeTime(_))
+            && self.config.coloring == ColorMode::Histogram
+            && !self.config.bailout_view;
+        match counted {
+            true => self.config.supersampling.max(1).pow(2) as usize,
+            false => 0,
+        }
+    }
+
+    /// Colors the pixels of `area` from the rank of the iterations of their samples among all the ones of the area,
+    /// for [ColorMode::Histogram], once `counts` holds the iterations left of the samples of each pixel.
+    ///
+    /// The mirrored rows are counted as their source rows, so the ranks are the ones of the whole area.
+    pub fn color_by_rank(&self, frame: &mut [u32], counts: &[u32], area: Tile) {
+        let (width, samples) = (self.viewport.width as usize, self.counted_samples());
+        let (left, right) = (area.x as usize, (area.x + area.width) as usize);
+        let rows = area.y as usize..(area.y + area.height) as usize;
+        let max = self.config.max_iterations;
+        let row_counts = |y: usize| {
+            let row = self.mirror_of(y).unwrap_or(y) * width;
+            &counts[(row + left) * samples..(row + right) * samples]
+        };
+
+        let mut histogram = vec![0u32; max as usize + 1];
+        for &count in rows.clone().flat_map(row_counts) {
+            if count != 0 {
+                histogram[max.saturating_sub(count) as usize] += 1;
+            }
+        }
+        let escaped: u32 = histogram.iter().sum();
+        if escaped == 0 {
+            // every pixel is already colored as inside
+            return;
+        }
+        let mut below = 0;
+        let cumulative: Vec<f64> = histogram
+            .iter()
+            .map(|&count| {
+                below += count;
+                below as f64 / escaped as f64
+            })
+            .collect();
+
+        for y in rows.filter(|&y| self.mirror_of(y).is_none()) {
+            let pixels = &mut frame[y * width + left..y * width + right];
+            for ((pixel, counts), x) in pixels
+                .iter_mut()
+                .zip(row_counts(y).chunks(samples))
+                .zip(left..)
+            {
+                let mut counts = counts.iter();
+                *pixel = self.supersample(x as f64, y as f64, |_, _| match counts.next() {
+                    Some(&count) if count != 0 => {
+                        let done = max.saturating_sub(count) as usize;
+                        self.config.palette_color_at(cumulative[done])
+                    }
+                    _ => self.config.inside_color,
+                });
+            }
+        }
+        self.render_mirrored(frame, area);
+    }
+
+    /// Color of an iterated point, with the distances in pixels of the view for [ColorMode::Distance].
+    fn color(&self, escape: &Escape) -> u32 {
+        if escape.count == 0 || self.config.bailout_view {
+            return self.config.color(escape);
+        }
+        match self.config.coloring {
+            ColorMode::Distance => {
+                let norm = escape.z.norm();
+                let distance = norm * norm.ln() / escape.dz.norm() / self.viewport.pixel_size();
+                // an overflowed `z` or `z'` gives no distance, the point is taken as far from the set
+                let t = if distance.is_nan() {
+                    1.0
+                } else {
+                    (distance / DISTANCE_FALLOFF).min(1.0)
+                };
+                self.config.palette_color_at(1.0 - t.sqrt())
+            }
+            _ => self.config.color(escape),
+        }
+    }
+
+    /// Tile covering the whole frame.
+    pub fn frame(&self) -> Tile {
+        Tile {
+            x: 0,
+            y: 0,
+            width: self.viewport.width,
+            height: self.viewport.height,
+        }
+    }
+
+    /// Tiles of `width` by `height` pixels covering `area`, row after row.
+    ///
+    /// When the frame is mirrored, `area` must be centered vertically so the mirrored rows stay inside of it.
+    pub fn tiles_in(&self, area: Tile, width: i32, height: i32) -> Vec<Tile> {
+        if !self.mode.pixel_by_pixel() {
+            return vec![self.frame()];
+        }
+
+        let (right, bottom) = (area.x + area.width, area.y + area.height);
+        let mut tiles = Vec::new();
+        for y in (area.y..bottom).step_by(height.max(1) as usize) {
+            for x in (area.x..right).step_by(width.max(1) as usize) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: width.min(right - x),
+                    height: height.min(bottom - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Side of square tiles taking about [TILE_TIME] to render, estimated by timing a sample of pixels.
+    pub fn tile_size(&self) -> i32 {
+        if !self.mode.pixel_by_pixel() {
+            return MAX_TILE_SIZE;
+        }
+        let (width, height) = (self.viewport.width, self.viewport.height);
+
+        let start = Instant::now();
+        for sy in 0..COST_SAMPLES {
+            for sx in 0..COST_SAMPLES {
+                let (x, y) = (sx * width / COST_SAMPLES, sy * height / COST_SAMPLES);
+                black_box(self.pixel(x as f64, y as f64));
+            }
+        }
+        let pixel_time = start.elapsed().as_secs_f64() / (COST_SAMPLES * COST_SAMPLES) as f64;
+
+        let size = (TILE_TIME.as_secs_f64() / pixel_time.max(f64::MIN_POSITIVE)).sqrt();
+        (size as i32).clamp(MIN_TILE_SIZE, MAX_TILE_SIZE)
+    }
+
+    /// Renders the pixels of `tile` into `frame`, and the iterations left of their samples into `counts`,
+    /// [counted_samples](Renderer::counted_samples) for each pixel of the frame, empty when there are none.
+    ///
+    /// The tiles must be rendered in the order given by [tiles_in](Renderer::tiles_in),
+    /// as mirrored pixels are copied from the rows above.
+    pub fn render_tile(&self, frame: &mut [u32], counts: &mut [u32], tile: Tile) {
+        let (viewport, config) = (&self.viewport, &self.config);
+        match self.mode {
+            Mode::EscapeTime(_) | Mode::Lyapunov | Mode::Newton => {}
+            Mode::Sierpinski => return sierpinski::chaos_game(viewport, config, frame, None),
+            Mode::Koch => return koch::draw(viewport, config, frame),
+        }
+        let (width, samples) = (viewport.width as usize, self.counted_samples());
+        let (left, right) = (tile.x as usize, (tile.x + tile.width) as usize);
+
+        for y in tile.y as usize..(tile.y + tile.height) as usize {
+            let row = y * width;
+            match self.mirror_of(y) {
+                Some(source) => {
+                    let source = source * width;
+                    frame.copy_within(source + left..source + right, row + left);
+                }
+                None => self.render_row(
+                    y,
+                    tile.x,
+                    &mut frame[row + left..row + right],
+                    &mut counts[(row + left) * samples..(row + right) * samples],
+                ),
+            }
+        }
+    }
+
+    /// Renders `tile` in blocks of [COARSE_BLOCK] pixels on each side, each one the color of its middle pixel,
+    /// `rows` starting at the first row of the tile.
+    ///
+    /// The blocks starting on a mirrored row are skipped, the rows are copied by [render_mirrored](Renderer::render_mirrored).
+    fn render_blocks(&self, rows: &mut [u32], tile: Tile) {
+        let width = self.viewport.width as usize;
+        let (right, bottom) = (tile.x + tile.width, tile.y + tile.height);
+        for by in (tile.y..bottom).step_by(COARSE_BLOCK as usize) {
+            if self.mirror_of(by as usize).is_some() {
+                continue;
+            }
+            let block_height = COARSE_BLOCK.min(bottom - by);
+            for bx in (tile.x..right).step_by(COARSE_BLOCK as usize) {
+                let block_width = COARSE_BLOCK.min(right - bx);
+                let middle = (
+                    (bx + block_width / 2) as f64,
+                    (by + block_height / 2) as f64,
+                );
+                let color = self.pixel(middle.0, middle.1).unwrap_or_default();
+                for y in by..by + block_height {
+                    let start = (y - tile.y) as usize * width + bx as usize;
+                    rows[start..start + block_width as usize].fill(color);
+                }
+            }
+        }
+    }
+
+    /// Computes the rows of `band` that are not mirrored, `rows` and `counts` starting at its first row.
+    #[cfg(feature = "threads")]
+    fn render_band(&self, band: Tile, rows: &mut [u32], counts: &mut [u32]) {
+        let (width, samples) = (self.viewport.width as usize, self.counted_samples());
+        let (left, right) = (band.x as usize, (band.x + band.width) as usize);
+        for (y, row) in (band.y as usize..).zip(rows.chunks_mut(width)) {
+            if self.mirror_of(y).is_none() {
+                let row_counts = (y - band.y as usize) * width * samples;
+                self.render_row(
+                    y,
+                    band.x,
+                    &mut row[left..right],
+                    &mut counts[row_counts + left * samples..row_counts + right * samples],
+                );
+            }
+        }
+    }
+
+    /// Copies the mirrored rows of `tile`, once the rows above are rendered.
+    fn render_mirrored(&self, frame: &mut [u32], tile: Tile) {
+        let width = self.viewport.width as usize;
+        let (left, right) = (tile.x as usize, (tile.x + tile.width) as usize);
+        for y in tile.y as usize..(tile.y + tile.height) as usize {
+            if let Some(source) = self.mirror_of(y) {
+                let source = source * width;
+                frame.copy_within(source + left..source + right, y * width + left);
+            }
+        }
+    }
+
+    /// Row copied to the row `y`, when it is below the real axis of a mirrored frame.
+    fn mirror_of(&self, y: usize) -> Option<usize> {
+        let height = self.viewport.height as usize;
+        (self.mirrored && y > height / 2).then(|| height - y)
+    }
+
+    /// Computes the pixels of the row `y` starting at `x`, as many as `pixels` holds,
+    /// and the iterations left of their samples into `counts` unless it is empty.
+    fn render_row(&self, y: usize, x: i32, pixels: &mut [u32], counts: &mut [u32]) {
+        let mut counts = counts.iter_mut();
+        for (pixel, x) in pixels.iter_mut().zip(x..) {
+            *pixel = self.supersample(x as f64, y as f64, |x, y| match self.mode {
+                Mode::EscapeTime(fractal) => {
+                    let escape = self.escape(FRACTALS[fractal], x, y);
+                    if let Some(count) = counts.next() {
+                        *count = escape.count;
+                    }
+                    self.color(&escape)
+                }
+                _ => self.pixel(x, y).unwrap_or_default(),
+            });
+        }
+    }
+
+    /// Color of the pixel `(x, y)`, the average of the colors given by `sample` at [RenderConfig::supersampling]
+    /// positions on each side, on a grid centered on the pixel, row after row.
+    ///
+    /// The channels are averaged squared, roughly in linear light, so the edges do not come out too dark.
+    fn supersample(&self, x: f64, y: f64, mut sample: impl FnMut(f64, f64) -> u32) -> u32 {
+        let factor = self.config.supersampling.max(1);
+        if factor == 1 {
+            return sample(x, y);
+        }
+        let mut sum = [0.0; 3];
+        for sy in 0..factor {
+            for sx in 0..factor {
+                let offset = |i: u32| (i as f64 + 0.5) / factor as f64 - 0.5;
+                let color = sample(x + offset(sx), y + offset(sy));
+                for (sum, shift) in sum.iter_mut().zip([16, 8, 0]) {
+                    *sum += ((color >> shift & 0xff) as f64).powi(2);
+                }
+            }
+        }
+        let count = (factor * factor) as f64;
+        sum.iter().zip([16, 8, 0]).fold(0, |color, (sum, shift)| {
+            color | ((sum / count).sqrt().round() as u32) << shift
+        })
+    }
+
+    /// Color at the position `(x, y)` in pixels, which may fall between pixels,
+    /// `None` when the mode is not computed [pixel by pixel](Mode::pixel_by_pixel).
+    pub fn pixel(&self, x: f64, y: f64) -> Option<u32> {
+        let (viewport, config) = (&self.viewport, &self.config);
+        match self.mode {
+            Mode::EscapeTime(fractal) => Some(self.color(&self.escape(FRACTALS[fractal], x, y))),
+            Mode::Lyapunov => Some(lyapunov::color(viewport.pixel_to_complex(x, y), config)),
+            Mode::Newton => Some(newton::color(viewport.pixel_to_complex(x, y), config)),
+            Mode::Sierpinski | Mode::Koch => None,
+        }
+    }
+
+    fn escape(&self, fractal: &dyn EscapeTime, x: f64, y: f64) -> Escape {
+        let (viewport, config) = (&self.viewport, &self.config);
+        match &self.orbit {
+            Some(orbit) => perturbation::escape(fractal, orbit, viewport, config, (x, y)),
+            None => fractal.escape(viewport.pixel_to_complex(x, y), config),
+        }
+    }
+}
+
+/// A frame rendered a few tiles at a time, see [Renderer].
+///
+/// With the `threads` feature, the tiles are bands of rows shared between a thread per core,
+/// see [step](TiledRender::step).
+pub struct TiledRender {
+    renderer: Renderer,
+    area: Tile,
+    tiles: Vec<Tile>,
+    /// iterations left of the samples of each pixel of the frame, for [ColorMode::Histogram],
+    /// see [Renderer::render_tile]
+    counts: Vec<u32>,
+    /// number of tiles at the start of `tiles` rendered in blocks, see [TiledRender::new]
+    coarse: usize,
+    next: usize,
+}
+
+impl TiledRender {
+    /// Starts rendering with square tiles of side `tile_size`, picked from the cost of the pixels when `None`.
+    ///
+    /// Only `area` is rendered when given, see [Renderer::tiles_in].
+    ///
+    /// With `coarse`, the area is first rendered in blocks of [COARSE_BLOCK] pixels on each side, a pass
+    /// 64 times quicker than the full one which refines it afterwards: the whole view shows up right away
+    /// when it changes, even when the full render takes seconds.
+    ///
+    /// With [ColorMode::Histogram], the iterations of the samples are kept as the tiles are rendered,
+    /// and the area is colored again from their ranks after the last tile.
+    pub fn new(
+        renderer: Renderer,
+        tile_size: Option<i32>,
+        area: Option<Tile>,
+        coarse: bool,
+    ) -> Self {
+        let size = tile_size.unwrap_or_else(|| renderer.tile_size());
+        let area = area.unwrap_or_else(|| renderer.frame());
+        // a block costs a pixel, so the coarse tiles cover as many blocks as the full ones cover pixels
+        let coarse_size = size * COARSE_BLOCK;
+        // bands of whole rows are contiguous parts of the frame that can be lent to the threads,
+        // with about as many pixels as a square tile, and whole blocks for the coarse pass
+        #[cfg(feature = "threads")]
+        let (tiles, coarse_tiles) = {
+            let band_height = |size: i32, multiple: i32| {
+                let height = (size * size / area.width.max(1)).max(1);
+                (height + multiple - 1) / multiple * multiple
+            };
+            (
+                renderer.tiles_in(area, area.width, band_height(size, 1)),
+                renderer.tiles_in(area, area.width, band_height(coarse_size, COARSE_BLOCK)),
+            )
+        };
+        #[cfg(not(feature = "threads"))]
+        let (tiles, coarse_tiles) = (
+            renderer.tiles_in(area, size, size),
+            renderer.tiles_in(area, coarse_size, coarse_size),
+        );
+        let (tiles, coarse) = if coarse && renderer.mode.pixel_by_pixel() {
+            let count = coarse_tiles.len();
+            (coarse_tiles.into_iter().chain(tiles).collect(), count)
+        } else {
+            (tiles, 0)
+        };
+        let frame = renderer.frame();
+        let counts = vec![0; (frame.width * frame.height) as usize * renderer.counted_samples()];
+        Self {
+            renderer,
+            area,
+            tiles,
+            counts,
+            coarse,
+            next: 0,
+        }
+    }
+
+    /// Colors the area once all the tiles are rendered, when the colors depend on the whole area.
+    fn finish(&self, frame: &mut [u32]) {
+        if !self.counts.is_empty() {
+            self.renderer.color_by_rank(frame, &self.counts, self.area);
+        }
+    }
+
+    /// Renders tiles into `frame` until `budget` is spent or `interrupted` returns true, at least one.
+    /// Returns whether the frame is complete.
+    ///
+    /// `interrupted` is checked between tiles, typically for pending input: the step ends early
+    /// so the input is handled right away, and the render goes on from the next tile at the next step,
+    /// unless the input changed the view and the render is replaced.
+    /// A tile being rendered is always finished, so smaller tiles or a lower resolution
+    /// make the renders quicker to interrupt.
+    #[cfg(not(feature = "threads"))]
+    pub fn step(
+        &mut self,
+        frame: &mut [u32],
+        budget: Duration,
+        interrupted: &dyn Fn() -> bool,
+    ) -> bool {
+        let start = Instant::now();
+        while let Some(&tile) = self.tiles.get(self.next) {
+            if self.next < self.coarse {
+                let width = self.renderer.viewport.width as usize;
+                self.renderer
+                    .render_blocks(&mut frame[tile.y as usize * width..], tile);
+                self.renderer.render_mirrored(frame, tile);
+            } else {
+                self.renderer.render_tile(frame, &mut self.counts, tile);
+            }
+            self.next += 1;
+            if start.elapsed() >= budget || interrupted() {
+                break;
+            }
+        }
+        let complete = self.next == self.tiles.len();
+        if complete {
+            self.finish(frame);
+        }
+        complete
+    }
+
+    /// Renders bands into `frame` until `budget` is spent or `interrupted` returns true, at least one.
+    /// Returns whether the frame is complete.
+    ///
+    /// Each thread takes the next band from a shared counter, until the budget is spent, the step is cancelled,
+    /// or none are left. The calling thread checks `interrupted` while they work, and cancels the step
+    /// by raising a flag the threads check between bands.
+    /// The frame is split into the rows of each band beforehand with `split_at_mut`,
+    /// so the threads write to disjoint slices and the borrow checker proves they cannot race.
+    /// Mirrored rows may be copied from a band another thread is computing,
+    /// so they are skipped by the threads and copied once they are all done.
+    /// A step does not go past the end of the coarse pass, whose bands overlap the ones of the full pass.
+    #[cfg(feature = "threads")]
+    pub fn step(
+        &mut self,
+        frame: &mut [u32],
+        budget: Duration,
+        interrupted: &dyn Fn() -> bool,
+    ) -> bool {
+        if !self.renderer.mode.pixel_by_pixel() {
+            // the chaos game and the lines are a single tile
+            self.renderer.render_tile(frame, &mut [], self.tiles[0]);
+            self.next = self.tiles.len();
+            return true;
+        }
+        let (width, samples) = (
+            self.renderer.viewport.width as usize,
+            self.renderer.counted_samples(),
+        );
+        let coarse = self.next < self.coarse;
+        let end = if coarse {
+            self.coarse
+        } else {
+            self.tiles.len()
+        };
+
+        let mut bands = Vec::new();
+        let (mut rest, mut rest_counts, mut rest_y) = (&mut frame[..], &mut self.counts[..], 0);
+        for &band in &self.tiles[self.next..end] {
+            let skipped = band.y as usize - rest_y;
+            let (_, rows) = rest.split_at_mut(skipped * width);
+            let (rows, after) = rows.split_at_mut(band.height as usize * width);
+            let (_, counts) = rest_counts.split_at_mut(skipped * width * samples);
+            let (counts, counts_after) =
+                counts.split_at_mut(band.height as usize * width * samples);
+            bands.push(Mutex::new(Some((band, rows, counts))));
+            (rest, rest_counts, rest_y) = (after, counts_after, (band.y + band.height) as usize);
+        }
+
+        let start = Instant::now();
+        let taken = AtomicUsize::new(0);
+        let cancelled = AtomicBool::new(false);
+        let threads = match self.renderer.config.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            threads => threads,
+        };
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    // checked before taking a band, so every band taken is rendered
+                    let stop = start.elapsed() >= budget || cancelled.load(Ordering::Relaxed);
+                    if stop && taken.load(Ordering::Relaxed) > 0 {
+                        break;
+                    }
+                    let Some(band) = bands.get(taken.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    let (band, rows, counts) = band.lock().unwrap().take().unwrap();
+                    // the coarse pass keeps no counts, the full one overwrites them
+                    if coarse {
+                        self.renderer.render_blocks(rows, band);
+                    } else {
+                        self.renderer.render_band(band, rows, counts);
+                    }
+                });
+            }
+            // once the budget is spent or all the bands are taken, the threads stop on their own
+            while start.elapsed() < budget && taken.load(Ordering::Relaxed) < bands.len() {
+                if interrupted() {
+                    cancelled.store(true, Ordering::Relaxed);
+                    break;
+                }
+                thread::sleep(INTERRUPT_POLL_INTERVAL);
+            }
+        });
+
+        let done = taken.into_inner().min(bands.len());
+        for band in &self.tiles[self.next..self.next + done] {
+            self.renderer.render_mirrored(frame, *band);
+        }
+        self.next += done;
+        let complete = self.next == self.tiles.len();
+        if complete {
+            self.finish(frame);
+        }
+        complete
+    }
+}
+
+/// Reports that `done` parts out of `total` are rendered.
+pub fn report(progress: &mut Option<&mut dyn FnMut(f32)>, done: usize, total: usize) {
+    if let Some(progress) = progress {
+        progress(done as f32 / total as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Centered view of the Mandelbrot set, which the renderer mirrors unless the coloring tells apart the conjugates.
+    fn mandelbrot(width: i32, height: i32, coloring: ColorMode) -> Renderer {
+        let mode = Mode::from_name("mandelbrot").unwrap();
+        let viewport = mode.default_viewport(width, height);
+        let config = RenderConfig {
+            coloring,
+            ..RenderConfig::default()
+        };
+        Renderer::new(mode, &viewport, &config)
+    }
+
+    fn render(renderer: &Renderer) -> Vec<u32> {
+        let viewport = renderer.viewport;
+        let mut frame = vec![0; (viewport.width * viewport.height) as usize];
+        let mut counts = vec![0; frame.len() * renderer.counted_samples()];
+        for tile in renderer.tiles_in(renderer.frame(), 16, 16) {
+            renderer.render_tile(&mut frame, &mut counts, tile);
+        }
+        if !counts.is_empty() {
+            renderer.color_by_rank(&mut frame, &counts, renderer.frame());
+        }
+        frame
+    }
+
+    #[test]
+    fn mirrored_frame_matches_direct_render() {
+        for (width, height) in [(60, 40), (60, 41)] {
+            for coloring in ColorMode::ALL {
+                let mut renderer = mandelbrot(width, height, coloring);
+                assert_eq!(renderer.mirrored, coloring.mirror_safe(), "{}", coloring);
+                let mirrored = render(&renderer);
+                renderer.mirrored = false;
+                let direct = render(&renderer);
+                assert!(mirrored == direct, "{}x{} {}", width, height, coloring);
+            }
+        }
+    }
+
+    #[test]
+    fn histogram_ranks_every_pixel_of_the_frame() {
+        let mode = Mode::from_name("mandelbrot").unwrap();
+        let config = RenderConfig {
+            coloring: ColorMode::Histogram,
+            ..RenderConfig::default()
+        };
+        for (width, height) in [(60, 40), (60, 41)] {
+            let viewport = mode.default_viewport(width, height);
+            let mut frame = vec![0; (width * height) as usize];
+            render_fractal(mode, &viewport, &config, &mut frame, None);
+
+            // the red channel of the default palette is the rank, which is the fraction of the pixels up to it
+            let mut ranks: Vec<u32> = frame
+                .iter()
+                .filter(|&&color| color != config.inside_color)
+                .map(|color| color >> 16)
+                .collect();
+            ranks.sort();
+            for (below, &rank) in ranks.iter().enumerate() {
+                if ranks.get(below + 1) != Some(&rank) {
+                    let fraction = (below + 1) as f64 / ranks.len() as f64;
+                    assert!(
+                        (fraction * 255.0 - rank as f64).abs() <= 0.5,
+                        "{}x{}",
+                        width,
+                        height
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn palette_color_covers_the_channels_without_wrapping() {
+        let config = RenderConfig::default();
+        // escaped right away, no iteration done
+        assert_eq!(config.palette_color(100, 100), 0x000000);
+        // half of the iterations done: 255 * 0.5, 255 * 0.25 and 255 * √0.5
+        assert_eq!(config.palette_color(50, 100), 0x8040b4);
+        // escaped on the last iteration of a long run, past where the shifts used to wrap
+        assert_eq!(config.palette_color(1, 10_000), 0xffffff);
+    }
+
+    #[test]
+    fn points_never_escaping_get_the_inside_color() {
+        let config = RenderConfig {
+            inside_color: 0x123456,
+            ..RenderConfig::default()
+        };
+        let escape = Escape {
+            count: 0,
+            z: Complex::new(0.1, 0.2),
+            dz: Complex::new(0.0, 0.0),
+            trap: 0.0,
+        };
+        assert_eq!(config.color(&escape), 0x123456);
+    }
+
+    /// `cargo test --release -- --ignored --nocapture mirror_benchmark`
+    #[test]
+    #[ignore]
+    fn mirror_benchmark() {
+        let mut renderer = mandelbrot(800, 600, ColorMode::Linear);
+        for mirrored in [false, true] {
+            renderer.mirrored = mirrored;
+            let start = Instant::now();
+            black_box(render(&renderer));
+            println!("mirrored {}: {:?}", mirrored, start.elapsed());
+        }
+    }
+}
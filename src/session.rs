@@ -0,0 +1,253 @@
+//! Save and restore everything needed to reproduce the current screen.
+//!
+//! A session file is a flat TOML file:
+//!
+//! ```toml
+//! version = 2
+//! fractal = "julia"    # name shown in the HUD
+//! center_re = 0.0       # complex number at the center of the window
+//! center_re_lo = 0.0    # what `center_re` misses of it, for deep zooms
+//! center_im = 0.0
+//! center_im_lo = 0.0
+//! scale = 2.0           # height of the visible region in the complex plane
+//! max_iterations = 110
+//! auto_iterations = false  # raise the iterations as the view is zoomed in
+//! max_auto_iterations = 2000
+//! escape_radius = 2.0
+//! julia_re = -0.9       # constant of the julia set
+//! julia_im = 0.27015
+//! points = 200000       # points plotted by the chaos game
+//! depth = 4             # subdivisions of the koch snowflake
+//! sequence = "AABAB"    # order of the rates `a` and `b` of the lyapunov fractal
+//! perturbation = false  # deep zoom renderer for the mandelbrot set
+//! inside_color = "#000000"  # color of the points that never escape
+//! precision = "f64"     # floating point type of the iterations, "f32" or "f64"
+//! coloring = "linear"   # "linear" or "logarithmic" spread of the iterations over the palette,
+//!                       # "angle", "potential", "smooth", "histogram", "distance" or "trap"
+//! palette = "default"   # "fire", "ocean", "grayscale", "rainbow" or "random-<seed>"
+//! palette_offset = 0.0  # rotation of the palette, as a fraction of its length
+//! palette_contrast = 1.0  # times the palette is stretched over the iterations
+//! ```
+//!
+//! Missing fields take their default value and are reported when loading.
+//! New fields may be added, but existing ones keep their name and meaning.
+//!
+//! Version 1 files stored the size of the visible region as `span_re` and `span_im`,
+//! `span_im` is still read as the scale when `scale` is missing.
+
+use crate::double_double::DoubleDouble;
+use crate::fractal::Mode;
+use crate::render::{RenderConfig, MAX_DEPTH, MAX_POINTS, MIN_POINTS};
+use crate::toml;
+use crate::viewport::Viewport;
+use num_complex::Complex;
+use std::fmt::Display;
+use std::fs;
+use std::str::FromStr;
+
+pub const SESSION_FILE: &str = "fractol-session.toml";
+
+const VERSION: u32 = 2;
+
+pub struct Session {
+    pub fractal: Mode,
+    pub viewport: Viewport,
+    pub config: RenderConfig,
+}
+
+impl Session {
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut writer = toml::Writer::default();
+        writer
+            .comment("fractol session, load it with `fractol --load <file>`")
+            .value("version", VERSION)
+            .string("fractal", self.fractal.name())
+            .value("center_re", self.viewport.center.re.hi)
+            .value("center_re_lo", self.viewport.center.re.lo)
+            .value("center_im", self.viewport.center.im.hi)
+            .value("center_im_lo", self.viewport.center.im.lo)
+            .value("scale", self.viewport.scale)
+            .value("max_iterations", self.config.max_iterations)
+            .value("auto_iterations", self.config.auto_iterations)
+            .value("max_auto_iterations", self.config.max_auto_iterations)
+            .value("escape_radius", self.config.escape_radius)
+            .value("julia_re", self.config.julia_constant.re)
+            .value("julia_im", self.config.julia_constant.im)
+            .value("points", self.config.points)
+            .value("depth", self.config.depth)
+            .string("sequence", &self.config.sequence.to_string())
+            .value("perturbation", self.config.perturbation)
+            .string(
+                "inside_color",
+                &format!("#{:06x}", self.config.inside_color),
+            )
+            .string("precision", &self.config.precision.to_string())
+            .string("coloring", self.config.coloring.name())
+            .string("palette", &self.config.palette.to_string())
+            .value("palette_offset", self.config.palette_offset)
+            .value("palette_contrast", self.config.palette_contrast);
+        fs::write(path, writer.finish()).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Reads a session for a window of `width` by `height` pixels.
+    ///
+    /// Missing and unknown fields are reported on stderr, invalid values are an error.
+    pub fn load(path: &str, width: i32, height: i32) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let table = toml::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let fields = Fields {
+            path,
+            table: &table,
+        };
+
+        for key in table.keys() {
+            if !KEYS.contains(&key) {
+                eprintln!("{}: unknown field `{}` ignored", path, key);
+            }
+        }
+
+        let version: u32 = fields.get("version", VERSION)?;
+        if version > VERSION {
+            return Err(format!("{}: unsupported session version {}", path, version));
+        }
+        let name: String = fields.get("fractal", Mode::EscapeTime(0).name().to_string())?;
+        let fractal = Mode::from_name(&name)
+            .ok_or_else(|| format!("{}: unknown fractal `{}`", path, name))?;
+
+        // version 1 files only have `span_im`
+        let scale_key = match (table.get("scale"), table.get("span_im")) {
+            (None, Some(_)) => "span_im",
+            _ => "scale",
+        };
+        let default_viewport = Viewport::new(width, height);
+        let default_config = RenderConfig::default();
+        let viewport = Viewport {
+            center: Complex::new(
+                fields.double_double("center_re", default_viewport.center.re)?,
+                fields.double_double("center_im", default_viewport.center.im)?,
+            ),
+            scale: fields.positive(scale_key, default_viewport.scale)?,
+            ..default_viewport
+        };
+        let config = RenderConfig {
+            max_iterations: fields.get("max_iterations", default_config.max_iterations)?,
+            auto_iterations: fields.get("auto_iterations", default_config.auto_iterations)?,
+            max_auto_iterations: fields
+                .get("max_auto_iterations", default_config.max_auto_iterations)?,
+            escape_radius: fields.positive("escape_radius", default_config.escape_radius)?,
+            julia_constant: Complex::new(
+                fields.get("julia_re", default_config.julia_constant.re)?,
+                fields.get("julia_im", default_config.julia_constant.im)?,
+            ),
+            points: fields.clamped("points", default_config.points, MIN_POINTS, MAX_POINTS)?,
+            // the koch snowflake draws 4^depth segments
+            depth: fields.clamped("depth", default_config.depth, 0, MAX_DEPTH)?,
+            sequence: fields.get("sequence", default_config.sequence)?,
+            perturbation: fields.get("perturbation", default_config.perturbation)?,
+            inside_color: fields.color("inside_color", default_config.inside_color)?,
+            precision: fields.get("precision", default_config.precision)?,
+            coloring: fields.get("coloring", default_config.coloring)?,
+            palette: fields.get("palette", default_config.palette)?,
+            palette_offset: fields.get("palette_offset", default_config.palette_offset)?,
+            palette_contrast: fields
+                .positive("palette_contrast", default_config.palette_contrast)?,
+            // the debugging view, the supersampling and the threads are not part of the view
+            ..default_config
+        };
+
+        Ok(Self {
+            fractal,
+            viewport,
+            config,
+        })
+    }
+}
+
+const KEYS: [&str; 25] = [
+    "version",
+    "fractal",
+    "center_re",
+    "center_re_lo",
+    "center_im",
+    "center_im_lo",
+    "scale",
+    // version 1
+    "span_re",
+    "span_im",
+    "max_iterations",
+    "auto_iterations",
+    "max_auto_iterations",
+    "escape_radius",
+    "julia_re",
+    "julia_im",
+    "points",
+    "depth",
+    "sequence",
+    "perturbation",
+    "inside_color",
+    "precision",
+    "coloring",
+    "palette",
+    "palette_offset",
+    "palette_contrast",
+];
+
+/// Typed access to the fields of a session file.
+struct Fields<'a> {
+    path: &'a str,
+    table: &'a toml::Table,
+}
+
+impl Fields<'_> {
+    fn get<T: FromStr + Display>(&self, key: &str, default: T) -> Result<T, String> {
+        match self.table.get(key) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("{}: invalid value `{}` for `{}`", self.path, value, key)),
+            None => {
+                eprintln!("{}: missing field `{}`, using {}", self.path, key, default);
+                Ok(default)
+            }
+        }
+    }
+
+    fn positive(&self, key: &str, default: f64) -> Result<f64, String> {
+        let value = self.get(key, default)?;
+        if value > 0.0 && value.is_finite() {
+            Ok(value)
+        } else {
+            Err(format!("{}: `{}` must be positive", self.path, key))
+        }
+    }
+
+    /// Reads a double-double number from `key` and its low part from `<key>_lo`.
+    fn double_double(&self, key: &str, default: DoubleDouble) -> Result<DoubleDouble, String> {
+        let hi = DoubleDouble::new(self.get(key, default.hi)?);
+        let lo = DoubleDouble::new(self.get(&format!("{}_lo", key), default.lo)?);
+        // renormalized, in case the file was edited by hand
+        Ok(hi.add(lo))
+    }
+
+    /// Reads a value between `min` and `max`, the ones past them are brought back and reported.
+    fn clamped(&self, key: &str, default: u32, min: u32, max: u32) -> Result<u32, String> {
+        let value = self.get(key, default)?;
+        let clamped = value.clamp(min, max);
+        if clamped != value {
+            eprintln!(
+                "{}: `{}` out of {}..={}, using {}",
+                self.path, key, min, max, clamped
+            );
+        }
+        Ok(clamped)
+    }
+
+    /// Reads a `#rrggbb` color.
+    fn color(&self, key: &str, default: u32) -> Result<u32, String> {
+        let value: String = self.get(key, format!("#{:06x}", default))?;
+        value
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| format!("{}: `{}` must be a #rrggbb color", self.path, key))
+    }
+}
@@ -0,0 +1,131 @@
+//! Preferences kept between launches, in `$XDG_CONFIG_HOME/fractol/settings.toml`,
+//! or `~/.config/fractol/settings.toml` when `XDG_CONFIG_HOME` is not set:
+//!
+//! ```toml
+//! width = 1080         # size of the window
+//! height = 720
+//! palette = "default"  # "fire", "ocean", "grayscale", "rainbow" or "random-<seed>"
+//! threads = 0          # render threads with the `threads` feature, 0 for one per core
+//! ```
+//!
+//! The file is created with the defaults on the first run, and saved again when a setting
+//! is changed from the keyboard. The command line options override it (`--size` the window size),
+//! and so does a session loaded with `--load` (the palette).
+//! Invalid values are reported on stderr and replaced with the defaults.
+
+use crate::palette::Palette;
+use crate::toml;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const SETTINGS_FILE: &str = "fractol/settings.toml";
+
+pub struct Settings {
+    pub size: (i32, i32),
+    pub palette: Palette,
+    /// render threads, 0 for one per core
+    pub threads: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            size: (1080, 720),
+            palette: Palette::Default,
+            threads: 0,
+        }
+    }
+}
+
+impl Settings {
+    /// Location of the file, `None` when neither `XDG_CONFIG_HOME` nor `HOME` is set.
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join(SETTINGS_FILE))
+    }
+
+    /// Reads the file, or creates it with the defaults when it does not exist yet.
+    ///
+    /// The problems are reported on stderr, the settings are then the defaults.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let path = path.display().to_string();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let settings = Self::default();
+                match settings.save() {
+                    Ok(()) => println!("settings saved to {}", path),
+                    Err(e) => eprintln!("{}", e),
+                }
+                return settings;
+            }
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                return Self::default();
+            }
+        };
+        let table = match toml::parse(&text) {
+            Ok(table) => table,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                return Self::default();
+            }
+        };
+
+        let default = Self::default();
+        let width = read(&path, &table, "width", default.size.0);
+        let height = read(&path, &table, "height", default.size.1);
+        Self {
+            size: if width > 0 && height > 0 {
+                (width, height)
+            } else {
+                eprintln!("{}: the window size must be positive", path);
+                default.size
+            },
+            palette: read(&path, &table, "palette", default.palette),
+            threads: read(&path, &table, "threads", default.threads),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("no config directory, set XDG_CONFIG_HOME or HOME")?;
+        let error = |e: io::Error| format!("{}: {}", path.display(), e);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(error)?;
+        }
+
+        let mut writer = toml::Writer::default();
+        writer
+            .comment("fractol settings, the command line options override them")
+            .value("width", self.size.0)
+            .value("height", self.size.1)
+            .string("palette", &self.palette.to_string())
+            .comment("render threads with the `threads` feature, 0 for one per core")
+            .value("threads", self.threads);
+        fs::write(&path, writer.finish()).map_err(error)
+    }
+}
+
+/// Value of `key`, or `default` when it is missing or invalid, which is reported.
+fn read<T: FromStr + Display>(path: &str, table: &toml::Table, key: &str, default: T) -> T {
+    let Some(value) = table.get(key) else {
+        return default;
+    };
+    value.parse().unwrap_or_else(|_| {
+        eprintln!(
+            "{}: invalid value `{}` for `{}`, using {}",
+            path, value, key, default
+        );
+        default
+    })
+}
@@ -0,0 +1,51 @@
+use crate::render::{self, RenderConfig};
+use crate::rng::Rng;
+use crate::viewport::Viewport;
+use num_complex::Complex;
+
+const SEED: u64 = 42;
+/// first points of the game, before it reaches the triangle
+const SKIPPED_POINTS: u32 = 20;
+/// number of times the progress is reported
+const PROGRESS_STEPS: u32 = 100;
+
+/// Corners of an equilateral triangle 2 units high, pointing up around the origin.
+const CORNERS: [Complex<f64>; 3] = [
+    Complex::new(0.0, -1.0),
+    Complex::new(-1.154_700_538_379_251_7, 1.0),
+    Complex::new(1.154_700_538_379_251_7, 1.0),
+];
+
+/// Plots the Sierpinski triangle with the chaos game into `frame`.
+///
+/// Starting from any point, jumping halfway to a randomly chosen corner over and over only ever lands on the triangle.
+/// Each point is colored by the corner it jumped to, spread over the palette.
+pub fn chaos_game(
+    viewport: &Viewport,
+    config: &RenderConfig,
+    frame: &mut [u32],
+    mut progress: Option<&mut dyn FnMut(f32)>,
+) {
+    let mut rng = Rng::new(SEED);
+    let mut z = Complex::new(0.0, 0.0);
+    let total = config.points.saturating_add(SKIPPED_POINTS);
+    let step = (total / PROGRESS_STEPS).max(1);
+
+    frame.fill(0);
+    for i in 0..total {
+        if (i + 1) % step == 0 {
+            render::report(&mut progress, (i + 1) as usize, total as usize);
+        }
+        let corner = rng.below(CORNERS.len() as u64) as usize;
+        z = (z + CORNERS[corner]) / 2.0;
+        if i < SKIPPED_POINTS {
+            continue;
+        }
+
+        let (x, y) = viewport.complex_to_pixel(z);
+        if x >= 0.0 && y >= 0.0 && x < viewport.width as f64 && y < viewport.height as f64 {
+            let (i, max) = (corner as u32, CORNERS.len() as u32);
+            frame[y as usize * viewport.width as usize + x as usize] = config.palette_color(i, max);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Animation of the Julia set, moving its constant around a circle.
+
+use num_complex::Complex;
+use std::f64::consts::PI;
+use std::time::Duration;
+
+/// Radius of the circle, the Julia sets around it change a lot along the way.
+pub const SWEEP_RADIUS: f64 = 0.7885;
+/// Radians per second.
+pub const SWEEP_SPEED: f64 = 0.3;
+
+/// Julia constant going around the circle of `radius` centered on the origin.
+pub struct Sweep {
+    radius: f64,
+    speed: f64,
+    angle: f64,
+    running: bool,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self::new(SWEEP_RADIUS, SWEEP_SPEED)
+    }
+}
+
+impl Sweep {
+    /// Paused sweep starting from the negative real axis, going around at `speed` radians per second.
+    pub fn new(radius: f64, speed: f64) -> Self {
+        Self {
+            radius,
+            speed,
+            angle: PI,
+            running: false,
+        }
+    }
+
+    pub fn running(&self) -> bool {
+        self.running
+    }
+
+    /// Pauses or resumes the sweep.
+    pub fn toggle(&mut self) {
+        self.running = !self.running;
+    }
+
+    /// Moves the constant along for `elapsed`, unless paused.
+    pub fn advance(&mut self, elapsed: Duration) {
+        if self.running {
+            self.angle += self.speed * elapsed.as_secs_f64();
+        }
+    }
+
+    pub fn constant(&self) -> Complex<f64> {
+        Complex::from_polar(self.radius, self.angle)
+    }
+}
@@ -0,0 +1,67 @@
+//! Frame rate of the frames put to the window, averaged over the last ones.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Number of frames the frame rate is averaged over.
+pub const FRAME_SAMPLES: usize = 30;
+/// A longer gap between two frames is a pause, not a slow frame: the average starts over.
+const IDLE_GAP: Duration = Duration::from_millis(500);
+
+/// Time between the last frames.
+pub struct FrameTimer {
+    last: Option<Instant>,
+    samples: VecDeque<Duration>,
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self {
+            last: None,
+            samples: VecDeque::with_capacity(FRAME_SAMPLES),
+        }
+    }
+}
+
+impl FrameTimer {
+    /// Records a frame, to be called each time one is shown.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        match self.last.map(|last| now - last) {
+            Some(gap) if gap <= IDLE_GAP => {
+                if self.samples.len() == FRAME_SAMPLES {
+                    self.samples.pop_front();
+                }
+                self.samples.push_back(gap);
+            }
+            _ => self.samples.clear(),
+        }
+        self.last = Some(now);
+    }
+
+    /// Average time between two frames, `None` until two frames were recorded in a row.
+    pub fn frame_time(&self) -> Option<Duration> {
+        let total: Duration = self.samples.iter().sum();
+        total.checked_div(self.samples.len() as u32)
+    }
+
+    /// Average number of frames per second.
+    pub fn fps(&self) -> Option<f64> {
+        self.frame_time()
+            .filter(|time| !time.is_zero())
+            .map(|time| time.as_secs_f64().recip())
+    }
+}
+
+/// Frame rate and frame time, e.g. `24 fps, 41.7 ms`, or `- fps` without a measure.
+impl fmt::Display for FrameTimer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.fps(), self.frame_time()) {
+            (Some(fps), Some(time)) => {
+                write!(f, "{:.0} fps, {:.1} ms", fps, time.as_secs_f64() * 1000.0)
+            }
+            _ => write!(f, "- fps"),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Reader and writer for the flat subset of TOML used by the fractol files:
+//! one `key = value` per line, `#` comments, and string, number or boolean values.
+
+use std::fmt::Display;
+
+/// Parsed `key = value` lines, in file order.
+pub struct Table {
+    entries: Vec<(String, String)>,
+}
+
+impl Table {
+    /// Raw value of `key`, with the quotes of strings removed.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All the keys present in the file.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| k.as_str())
+    }
+}
+
+/// Parses the content of a file, the error tells which line is wrong.
+pub fn parse(text: &str) -> Result<Table, String> {
+    let mut entries = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+        let value = value.trim();
+        let value = match value.strip_prefix('"') {
+            Some(rest) => {
+                let (string, after) = rest
+                    .split_once('"')
+                    .ok_or_else(|| format!("line {}: unterminated string", number + 1))?;
+                let after = after.trim();
+                if !after.is_empty() && !after.starts_with('#') {
+                    return Err(format!(
+                        "line {}: unexpected `{}` after string",
+                        number + 1,
+                        after
+                    ));
+                }
+                string
+            }
+            None => value.split('#').next().unwrap_or_default().trim(),
+        };
+        entries.push((key.trim().to_string(), value.to_string()));
+    }
+    Ok(Table { entries })
+}
+
+/// Builds the content of a file, line by line.
+#[derive(Default)]
+pub struct Writer {
+    text: String,
+}
+
+impl Writer {
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.text.push_str(&format!("# {}\n", comment));
+        self
+    }
+
+    pub fn value(&mut self, key: &str, value: impl Display) -> &mut Self {
+        self.text.push_str(&format!("{} = {}\n", key, value));
+        self
+    }
+
+    pub fn string(&mut self, key: &str, value: &str) -> &mut Self {
+        self.text.push_str(&format!("{} = \"{}\"\n", key, value));
+        self
+    }
+
+    pub fn finish(&self) -> &str {
+        &self.text
+    }
+}
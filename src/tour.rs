@@ -0,0 +1,231 @@
+//! Tours, navigations recorded in the window and replayed smoothly,
+//! in the window or headlessly as numbered PNG frames (`fractol-tour-0001.png`...).
+//!
+//! A tour file has a keyframe per line: the time in seconds since the start of the recording,
+//! then the center and the scale of the view at that time.
+//!
+//! ```text
+//! # seconds center_re center_im scale
+//! 0 -0.5 0 2
+//! 1.25 -0.74 0.12 0.3
+//! ```
+//!
+//! Between two keyframes, the view zooms at constant speed like the zoom movies,
+//! see [zoom::interpolate]. The fractal and its settings are not part of the tour,
+//! they are the ones of the window, or of the command line.
+
+use crate::double_double;
+use crate::export::ExportConfig;
+use crate::fractal::Mode;
+use crate::render::RenderConfig;
+use crate::viewport::Viewport;
+use crate::zoom;
+use num_complex::Complex;
+use std::fs;
+use std::time::{Duration, Instant};
+
+pub const TOUR_FILE: &str = "fractol-tour.txt";
+
+/// Shortest time between two keyframes, a drag moving the view at every frame
+/// would otherwise give dozens of keyframes per second.
+const KEYFRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// View at a moment of the tour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    /// seconds since the start of the tour
+    pub time: f64,
+    pub center: Complex<f64>,
+    pub scale: f64,
+}
+
+/// Keyframes in chronological order, at least one.
+pub struct Tour {
+    keyframes: Vec<Keyframe>,
+}
+
+impl Tour {
+    /// Length of the tour in seconds.
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// View `time` seconds into the tour, for an image of `width` by `height` pixels.
+    ///
+    /// The views before the first keyframe and after the last one are the ones of these keyframes.
+    pub fn viewport(&self, time: f64, width: i32, height: i32) -> Viewport {
+        let view = |keyframe: &Keyframe| Viewport {
+            center: double_double::complex(keyframe.center),
+            scale: keyframe.scale,
+            ..Viewport::new(width, height)
+        };
+        let next = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time);
+        match next {
+            Some(0) => view(&self.keyframes[0]),
+            Some(i) => {
+                let (from, to) = (&self.keyframes[i - 1], &self.keyframes[i]);
+                let t = (time - from.time) / (to.time - from.time);
+                zoom::interpolate(&view(from), &view(to), t)
+            }
+            None => view(&self.keyframes[self.keyframes.len() - 1]),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut text = String::from("# fractol tour: seconds center_re center_im scale\n");
+        for keyframe in &self.keyframes {
+            let Keyframe {
+                time,
+                center,
+                scale,
+            } = keyframe;
+            text += &format!("{:.3} {} {} {}\n", time, center.re, center.im, scale);
+        }
+        fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Reads a tour, the keyframes must be in chronological order.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut keyframes: Vec<Keyframe> = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("{}: line {}: {}", path, number + 1, message);
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse().ok().filter(|v: &f64| v.is_finite()))
+                .collect::<Option<Vec<f64>>>();
+            let Some(&[time, re, im, scale]) = values.as_deref() else {
+                return Err(error(
+                    "expected `<seconds> <center_re> <center_im> <scale>`",
+                ));
+            };
+            if scale <= 0.0 {
+                return Err(error("the scale must be positive"));
+            }
+            if keyframes.last().is_some_and(|last| time < last.time) {
+                return Err(error("the keyframes must be in chronological order"));
+            }
+            keyframes.push(Keyframe {
+                time,
+                center: Complex::new(re, im),
+                scale,
+            });
+        }
+
+        if keyframes.is_empty() {
+            return Err(format!("{}: no keyframes", path));
+        }
+        Ok(Self { keyframes })
+    }
+
+    /// Renders and writes `fps` frames for each second of the tour, for images of `width` by `height` pixels.
+    pub fn render(
+        &self,
+        mode: Mode,
+        config: &RenderConfig,
+        export: &ExportConfig,
+        (width, height): (i32, i32),
+        fps: u32,
+    ) -> Result<(), String> {
+        let frames = (self.duration() * fps as f64) as u32 + 1;
+        let viewports: Vec<Viewport> = (0..frames)
+            .map(|i| self.viewport(i as f64 / fps as f64, width, height))
+            .collect();
+        zoom::render_frames("fractol-tour", mode, &viewports, config, export)
+    }
+}
+
+/// Tour being recorded, a keyframe being added whenever the view changes.
+pub struct Recorder {
+    start: Instant,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Recorder {
+    /// Starts recording from `viewport`.
+    pub fn new(viewport: &Viewport) -> Self {
+        let mut recorder = Self {
+            start: Instant::now(),
+            keyframes: Vec::new(),
+        };
+        recorder.push(viewport);
+        recorder
+    }
+
+    pub fn keyframes(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    /// Adds a keyframe if `viewport` moved since the last one, at most every [KEYFRAME_INTERVAL].
+    ///
+    /// A change is only recorded once the interval passed, so the view a quick movement ends on is not lost.
+    pub fn record(&mut self, viewport: &Viewport) {
+        let since_last = self.start.elapsed().as_secs_f64() - self.last().time;
+        if self.moved(viewport) && since_last >= KEYFRAME_INTERVAL.as_secs_f64() {
+            self.push(viewport);
+        }
+    }
+
+    /// Ends the recording on `viewport`.
+    pub fn finish(mut self, viewport: &Viewport) -> Tour {
+        if self.moved(viewport) {
+            self.push(viewport);
+        }
+        Tour {
+            keyframes: self.keyframes,
+        }
+    }
+
+    fn last(&self) -> &Keyframe {
+        // the first keyframe is pushed when the recording starts
+        &self.keyframes[self.keyframes.len() - 1]
+    }
+
+    fn moved(&self, viewport: &Viewport) -> bool {
+        let last = self.last();
+        last.center != double_double::round(viewport.center) || last.scale != viewport.scale
+    }
+
+    fn push(&mut self, viewport: &Viewport) {
+        self.keyframes.push(Keyframe {
+            time: self.start.elapsed().as_secs_f64(),
+            center: double_double::round(viewport.center),
+            scale: viewport.scale,
+        });
+    }
+}
+
+/// Tour being replayed in the window.
+pub struct Playback {
+    tour: Tour,
+    /// seconds played
+    time: f64,
+}
+
+impl Playback {
+    pub fn new(tour: Tour) -> Self {
+        Self { tour, time: 0.0 }
+    }
+
+    /// Moves the tour along for `elapsed`, returns whether it is over.
+    pub fn advance(&mut self, elapsed: Duration) -> bool {
+        self.time += elapsed.as_secs_f64();
+        self.time > self.tour.duration()
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn tour(&self) -> &Tour {
+        &self.tour
+    }
+}
@@ -0,0 +1,197 @@
+use crate::double_double::{self, DoubleDouble};
+use num_complex::Complex;
+
+/// Region of the complex plane mapped onto the image.
+///
+/// Pixels are square: the width of the region follows the aspect ratio of the image, so nothing is stretched.
+/// The real axis goes right and the imaginary axis goes down, like pixel coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    /// complex number at the center of the image, precise enough to move around at deep zooms
+    pub center: Complex<DoubleDouble>,
+    /// height of the region in the complex plane
+    pub scale: f64,
+    /// width of the image in pixels
+    pub width: i32,
+    /// height of the image in pixels
+    pub height: i32,
+}
+
+impl Viewport {
+    /// Default view, 2 units high around the origin.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            center: Complex::default(),
+            scale: 2.0,
+            width,
+            height,
+        }
+    }
+
+    /// Width of the region in the complex plane.
+    pub fn span_re(&self) -> f64 {
+        self.scale * self.width as f64 / self.height as f64
+    }
+
+    /// Height of the region in the complex plane.
+    pub fn span_im(&self) -> f64 {
+        self.scale
+    }
+
+    /// Size of a pixel in the complex plane.
+    pub fn pixel_size(&self) -> f64 {
+        self.scale / self.height as f64
+    }
+
+    /// Complex number under the pixel `(x, y)`.
+    pub fn pixel_to_complex(&self, x: f64, y: f64) -> Complex<f64> {
+        double_double::round(self.pixel_to_center(x, y))
+    }
+
+    /// Complex number under the pixel `(x, y)`, at the precision of the center.
+    fn pixel_to_center(&self, x: f64, y: f64) -> Complex<DoubleDouble> {
+        double_double::offset(self.center, self.pixel_offset(x, y))
+    }
+
+    /// Distance from the center to the complex number under the pixel `(x, y)`.
+    ///
+    /// Unlike [pixel_to_complex](Viewport::pixel_to_complex), it keeps its precision at any zoom level.
+    pub fn pixel_offset(&self, x: f64, y: f64) -> Complex<f64> {
+        Complex::new(x - 0.5 * self.width as f64, y - 0.5 * self.height as f64) * self.pixel_size()
+    }
+
+    /// Pixel position of the complex number `z`, which may lie outside of the image.
+    pub fn complex_to_pixel(&self, z: Complex<f64>) -> (f64, f64) {
+        let offset =
+            double_double::difference(double_double::complex(z), self.center) / self.pixel_size();
+        (
+            offset.re + 0.5 * self.width as f64,
+            offset.im + 0.5 * self.height as f64,
+        )
+    }
+
+    /// Whether the real axis goes through the middle of the image, each row then having a mirror row.
+    ///
+    /// The rows `y` and `height - y` are mirrored exactly, their complex numbers only differ by the sign of their imaginary part.
+    pub fn centered_on_real_axis(&self) -> bool {
+        self.center.im == DoubleDouble::default()
+    }
+
+    /// View of the same region with pixels `factor` times larger on each side.
+    ///
+    /// When the size is not a multiple of `factor`, the last pixels go past the right and bottom edges,
+    /// so each pixel of the result covers exactly `factor` by `factor` pixels of this view.
+    pub fn downscaled(&self, factor: i32) -> Self {
+        let (width, height) = (
+            (self.width + factor - 1) / factor,
+            (self.height + factor - 1) / factor,
+        );
+        let covered = ((width * factor) as f64, (height * factor) as f64);
+        Self {
+            center: self.pixel_to_center(0.5 * covered.0, 0.5 * covered.1),
+            scale: self.scale * covered.1 / self.height as f64,
+            width,
+            height,
+        }
+    }
+
+    /// Same region height around the same center, for an image of `width` by `height` pixels.
+    ///
+    /// The width of the region follows the new aspect ratio.
+    pub fn resized(&self, width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            ..*self
+        }
+    }
+
+    /// View of the `width` pixel columns starting at `x`, at the same scale.
+    pub fn columns(&self, x: i32, width: i32) -> Self {
+        let middle = (x as f64 + 0.5 * width as f64, 0.5 * self.height as f64);
+        Self {
+            center: self.pixel_to_center(middle.0, middle.1),
+            width,
+            ..*self
+        }
+    }
+
+    /// View moved by fractions of its width `dx` and of its height `dy`, right and down when positive.
+    ///
+    /// The distance follows the scale, so a step moves the view by as much of what is seen at any zoom level.
+    pub fn panned(&self, dx: f64, dy: f64) -> Self {
+        Self {
+            center: double_double::offset(
+                self.center,
+                Complex::new(dx * self.span_re(), dy * self.span_im()),
+            ),
+            ..*self
+        }
+    }
+
+    /// View `factor` times larger, or smaller below 1, keeping the complex number under the pixel `(x, y)` in place.
+    pub fn zoom_at(&self, x: f64, y: f64, factor: f64) -> Self {
+        // anchor + (center - anchor) * factor, with the anchor as an offset from the center
+        Self {
+            center: double_double::offset(self.center, self.pixel_offset(x, y) * (1.0 - factor)),
+            scale: self.scale * factor,
+            ..*self
+        }
+    }
+
+    /// View of the pixel rectangle between the corners `from` and `to`.
+    ///
+    /// The shorter side of the rectangle is extended so the view keeps the same aspect ratio.
+    pub fn select(&self, from: (i32, i32), to: (i32, i32)) -> Self {
+        let a = self.pixel_offset(from.0 as f64, from.1 as f64);
+        let b = self.pixel_offset(to.0 as f64, to.1 as f64);
+        let ratio = self.width as f64 / self.height as f64;
+        let span_re = (b.re - a.re).abs();
+        let span_im = (b.im - a.im).abs();
+
+        Self {
+            center: double_double::offset(self.center, (a + b) / 2.0),
+            scale: span_im.max(span_re / ratio),
+            ..*self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_are_square_at_any_aspect_ratio() {
+        for (width, height) in [(800, 800), (1000, 400), (300, 900)] {
+            let viewport = Viewport::new(width, height);
+            let (x, y, d) = (123.0, 45.0, 17.0);
+            let p = viewport.pixel_to_complex(x, y);
+            let along_x = (viewport.pixel_to_complex(x + d, y) - p).norm();
+            let along_y = (viewport.pixel_to_complex(x, y + d) - p).norm();
+            assert!((along_x - along_y).abs() < 1e-12, "{}x{}", width, height);
+        }
+    }
+
+    #[test]
+    fn the_center_moves_past_the_precision_of_f64() {
+        // a pixel is far below the spacing of the f64 around the center
+        let start = Viewport {
+            center: double_double::complex(Complex::new(-0.75, 0.1)),
+            scale: 1e-20,
+            ..Viewport::new(400, 400)
+        };
+
+        let panned = start.panned(0.5, -0.25);
+        let moved = double_double::difference(panned.center, start.center);
+        assert!((moved - Complex::new(0.5e-20, -0.25e-20)).norm() < 1e-30);
+
+        // the number under the corner stays in place
+        let zoomed = start.zoom_at(0.0, 0.0, 0.5);
+        let drift = double_double::difference(
+            zoomed.pixel_to_center(0.0, 0.0),
+            start.pixel_to_center(0.0, 0.0),
+        );
+        assert!(drift.norm() < 1e-30);
+    }
+}
@@ -0,0 +1,91 @@
+//! Several windows side by side, each showing a part of one wide view, for demos on several monitors.
+//!
+//! The windows are moved next to each other when they open, but the window manager has the last word
+//! on where they go: some ignore the request, and they may have to be dragged in place by hand.
+
+use crate::fractal::Mode;
+use crate::keymap::{Action, Keymap};
+use crate::render::{self, RenderConfig};
+use crate::viewport::Viewport;
+use mlx::{Mlx, MlxError, MlxImage, MlxWindow};
+use std::rc::Rc;
+
+/// The windows and the images holding their part of the view.
+pub struct Wall {
+    mlx: Mlx,
+    panes: Vec<(MlxWindow, MlxImage)>,
+}
+
+impl Wall {
+    /// Opens `count` windows of `width` by `height` pixels from left to right,
+    /// and renders the parts of `viewport` widened to span all of them.
+    pub fn new(
+        mlx: Mlx,
+        mode: Mode,
+        viewport: &Viewport,
+        config: &RenderConfig,
+        count: i32,
+        (width, height): (i32, i32),
+    ) -> Result<Self, String> {
+        let wide = Viewport {
+            width: width * count,
+            height,
+            ..*viewport
+        };
+        let mut panes = Vec::new();
+        for i in 0..count {
+            let title = format!("Fractol - {} ({}/{})", mode.name(), i + 1, count);
+            let window = mlx
+                .new_window(width, height, &title)
+                .map_err(|e| e.to_string())?;
+            mlx.move_window(&window, i * width, 0);
+            let mut image = mlx.new_image(width, height).map_err(|e| e.to_string())?;
+
+            let pane = wide.columns(i * width, width);
+            let mut frame = vec![0; (width * height) as usize];
+            render::render_fractal(mode, &pane, config, &mut frame, None);
+            for (row, frame_row) in image.rows_mut().zip(frame.chunks(width as usize)) {
+                row.copy_from_slice(frame_row);
+            }
+            panes.push((window, image));
+        }
+        Ok(Self { mlx, panes })
+    }
+
+    /// Shows the windows until the quit key is pressed or one of them is closed.
+    ///
+    /// The windows and images are freed when it returns.
+    pub fn run(self, keymap: Keymap) -> Result<(), MlxError> {
+        let wall = Rc::new(self);
+        for (window, _) in &wall.panes {
+            let state = wall.clone();
+            window.expose_hook(move |_| state.present(), &());
+
+            let state = wall.clone();
+            window.close_hook(move |_| state.quit(), &());
+
+            let (state, keymap) = (wall.clone(), keymap.clone());
+            window.key_press_hook(
+                move |keycode, _, _| {
+                    if keymap.action(keycode) == Some(Action::Quit) {
+                        state.quit();
+                    }
+                },
+                &(),
+            );
+        }
+        wall.present();
+        // the hooks drop their handles to the wall when the loop returns
+        wall.mlx.event_loop()
+    }
+
+    fn present(&self) {
+        for (window, image) in &self.panes {
+            self.mlx.put_image_to_window(window, image, 0, 0);
+        }
+    }
+
+    fn quit(&self) {
+        self.mlx.loop_end();
+    }
+}
@@ -0,0 +1,102 @@
+//! Zoom movies, rendered headlessly as numbered PNG frames.
+//!
+//! The frames can be assembled into a video with e.g.
+//! `ffmpeg -framerate 30 -i fractol-zoom-%04d.png zoom.mp4`.
+
+use crate::double_double;
+use crate::export::{self, ExportConfig};
+use crate::fractal::Mode;
+use crate::render::RenderConfig;
+use crate::viewport::Viewport;
+use std::time::{Duration, Instant};
+
+/// Zoom from a view to another one.
+#[derive(Clone, Copy, Debug)]
+pub struct ZoomSequence {
+    pub mode: Mode,
+    pub from: Viewport,
+    pub to: Viewport,
+    pub frames: u32,
+}
+
+impl ZoomSequence {
+    /// View of the frame `t` of the way from the first view to the last one, see [interpolate].
+    pub fn viewport(&self, t: f64) -> Viewport {
+        interpolate(&self.from, &self.to, t)
+    }
+
+    /// Renders and writes every frame, showing the progress and the time left on stderr.
+    pub fn render(&self, config: &RenderConfig, export: &ExportConfig) -> Result<(), String> {
+        let viewports: Vec<Viewport> = (0..self.frames)
+            .map(|i| self.viewport(i as f64 / (self.frames - 1).max(1) as f64))
+            .collect();
+        render_frames("fractol-zoom", self.mode, &viewports, config, export)
+    }
+}
+
+/// View `t` of the way from `from` to `to`.
+///
+/// The scale changes by the same factor at each step so the zoom looks constant-speed,
+/// and the center follows the scale so the point zoomed into stays in place on screen.
+pub fn interpolate(from: &Viewport, to: &Viewport, t: f64) -> Viewport {
+    let scale = from.scale * (to.scale / from.scale).powf(t);
+    let center = if from.scale == to.scale {
+        double_double::offset(
+            from.center,
+            double_double::difference(to.center, from.center) * t,
+        )
+    } else {
+        let t = (scale - to.scale) / (from.scale - to.scale);
+        double_double::offset(
+            to.center,
+            double_double::difference(from.center, to.center) * t,
+        )
+    };
+    Viewport {
+        center,
+        scale,
+        ..*from
+    }
+}
+
+/// Renders and writes a frame for each view, numbered after `prefix`,
+/// showing the progress and the time left on stderr.
+pub fn render_frames(
+    prefix: &str,
+    mode: Mode,
+    viewports: &[Viewport],
+    config: &RenderConfig,
+    export: &ExportConfig,
+) -> Result<(), String> {
+    let Some(first) = viewports.first() else {
+        return Ok(());
+    };
+    let size = export.size.unwrap_or((first.width, first.height));
+    let (start, frames) = (Instant::now(), viewports.len() as u32);
+
+    for (i, viewport) in (0..).zip(viewports) {
+        let path = format!("{}-{:04}.png", prefix, i + 1);
+        let left = match i {
+            0 => "?".to_string(),
+            _ => format_duration(start.elapsed() / i * (frames - i)),
+        };
+        let label = format!("{} ({}/{}, {} left)", path, i + 1, frames, left);
+
+        let pixels = export::render_antialiased(
+            mode,
+            viewport,
+            config,
+            size,
+            export,
+            Some(&mut export::progress_bar(&label)),
+        );
+        export::write_png(&path, size.0 as usize, &pixels)
+            .map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}m{:02}s", seconds / 60, seconds % 60)
+}